@@ -0,0 +1,359 @@
+//! # command
+//!
+//! `command` implements the vim-style command line opened with `:`, for
+//! things that don't need their own key, like `:rename <name>` or
+//! `:set timeout 30`. Commands and their arguments complete with Tab, and
+//! earlier commands can be brought back with the arrow keys.
+
+use std::{
+    fs,
+    path::Path,
+};
+
+use crossterm::event::{
+    Event,
+    KeyCode,
+    KeyEvent,
+    KeyModifiers,
+};
+use tui_input::{
+    backend::crossterm as input_backend,
+    Input,
+};
+
+use super::{
+    completion,
+    config::{
+        Action,
+        SETTINGS,
+    },
+    export::HAR_EXPORT_PATH,
+    history::Change,
+    request::ReqField,
+    theme::THEMES,
+    App,
+    InputStatus,
+};
+
+/// Commands that can be run, with the arguments they take
+pub const COMMANDS: [(&str, &str); 11] = [
+    ("new", "Create a request"),
+    ("rename", "<name>  Rename the selected request"),
+    ("delete", "Delete the selected request"),
+    ("env", "[name]  Switch to an environment, or to none"),
+    ("import", "<file>  Import a spec or HAR file"),
+    ("export", "[file]  Export requests as HAR"),
+    ("set", "<setting> <value>  Change a setting"),
+    ("close", "Close the tab"),
+    ("w", "Save the selected request"),
+    ("q", "Quit"),
+    ("wq", "Save the selected request and quit"),
+];
+
+/// The command being typed, and the commands run before it
+#[derive(Default)]
+pub struct CommandLine {
+    pub input: Input,
+    history: Vec<String>,
+    // Index in the history of the command shown, while going through it
+    history_index: Option<usize>,
+}
+
+impl App {
+    /// Open the command line
+    pub fn open_command_line(&mut self) {
+        self.command_line.input.reset();
+        self.command_line.history_index = None;
+        self.input_status = InputStatus::COMMAND;
+    }
+
+    /// Handle key events while typing a command
+    pub fn command_mode_keys(&mut self, modifiers: KeyModifiers,
+        code: KeyCode) {
+
+        match (self.config.keys.action(true, modifiers, code), code) {
+            (Some(Action::Cancel), _) =>
+                self.input_status = InputStatus::NORMAL,
+            (Some(Action::Submit), _) => {
+                self.input_status = InputStatus::NORMAL;
+
+                let text = self.command_line.input.value().trim().to_string();
+                if text.is_empty() {
+                    return;
+                }
+                if self.command_line.history.last() != Some(&text) {
+                    self.command_line.history.push(text.clone());
+                }
+
+                if let Err(e) = self.run_command(&text) {
+                    self.status_line = e;
+                }
+            },
+            (Some(Action::Complete), _) => self.complete_command(),
+            (Some(Action::Help), _) => self.show_help = true,
+            (_, KeyCode::Up) => self.recall_command(true),
+            (_, KeyCode::Down) => self.recall_command(false),
+            _ => {
+                input_backend::to_input_request(Event::Key(KeyEvent {
+                    modifiers,
+                    code,
+                }))
+                    .and_then(|req| self.command_line.input.handle(req));
+            },
+        }
+    }
+
+    /// Show an older or newer command from the history
+    fn recall_command(&mut self, older: bool) {
+        let line = &mut self.command_line;
+        let last = match line.history.len() {
+            0 => return,
+            len => len - 1,
+        };
+
+        line.history_index = match (line.history_index, older) {
+            (None, true) => Some(last),
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index < last => Some(index + 1),
+            _ => None,
+        };
+
+        line.input = match line.history_index {
+            Some(index) => Input::from(line.history[index].as_str()),
+            None => Input::default(),
+        };
+    }
+
+    /// Run a command typed in the command line
+    ///
+    /// Messages are shown in the status line, and errors are returned
+    pub fn run_command(&mut self, text: &str) -> Result<(), String> {
+        let (name, arg) = match text.split_once(' ') {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (text, ""),
+        };
+
+        match name {
+            "new" => self.new_request(),
+            "rename" if arg.is_empty() =>
+                return Err(String::from("Usage: :rename <name>")),
+            "rename" => self.rename_request(arg)?,
+            "delete" => self.delete_request(),
+            "env" if arg.is_empty() => self.env_index = None,
+            "env" => self.env_index = Some(self.environments.iter()
+                .position(|env| env.name == arg)
+                .ok_or(format!("Unknown environment \"{}\"", arg))?),
+            "import" if arg.is_empty() =>
+                return Err(String::from("Usage: :import <file>")),
+            "import" => {
+                let count = self.import_file(Path::new(arg))
+                    .map_err(|e| format!("{}", e))?;
+                self.status_line = format!("Imported {} requests", count);
+            },
+            "export" => {
+                let path = Path::new(if arg.is_empty() {
+                    HAR_EXPORT_PATH
+                } else {
+                    arg
+                });
+                let count = self.export_har(path)
+                    .map_err(|e| format!("{}", e))?;
+                self.status_line = format!("Exported {} requests to {}",
+                    count, path.display());
+            },
+            "set" => {
+                let (setting, value) = arg.split_once(' ')
+                    .ok_or("Usage: :set <setting> <value>")?;
+                if !SETTINGS.contains(&setting) {
+                    return Err(format!("Unknown setting \"{}\", the settings \
+                        are {}", setting, SETTINGS.join(", ")));
+                }
+
+                self.config.set(setting, value.trim())?;
+            },
+            "close" => self.close_tab()?,
+            "w" | "wq" if !self.requests.is_empty() => {
+                self.save_current_request();
+                if name == "wq" {
+                    self.is_running = false;
+                }
+            },
+            "w" => return Err(String::from("No request to save")),
+            "q" | "wq" => self.is_running = false,
+            _ => return Err(format!("Unknown command \"{}\"", name)),
+        }
+
+        Ok(())
+    }
+
+    /// Rename the selected request, so it can be undone like an edit
+    fn rename_request(&mut self, name: &str) -> Result<(), String> {
+        let cur_req = self.requests.get_mut(self.req_index)
+            .ok_or("No request to rename")?;
+
+        let before = cur_req.field_text(ReqField::Name);
+        cur_req.set_field_text(ReqField::Name, name);
+        self.history.record(Change::Edit {
+            index: self.req_index,
+            field: ReqField::Name,
+            before,
+            after: name.to_string(),
+        });
+
+        self.save_current_request();
+        self.update_inputs();
+        Ok(())
+    }
+
+    /// Complete the command or argument being typed
+    ///
+    /// Completes as far as all the matches agree, and shows the matches in
+    /// the status line when there are several
+    fn complete_command(&mut self) {
+        let text = self.command_line.input.value().to_string();
+        let (before, word) = match text.rsplit_once(' ') {
+            Some((before, word)) => (Some(before), word),
+            None => (None, text.as_str()),
+        };
+
+        let words: Option<Vec<&str>> = before
+            .map(|before| before.split_whitespace().collect());
+        let choices: Vec<String> = match words.as_deref() {
+            None => COMMANDS.iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            Some(["env"]) => self.environments.iter()
+                .map(|env| env.name.clone())
+                .collect(),
+            Some(["set"]) => SETTINGS.iter()
+                .map(|setting| setting.to_string())
+                .collect(),
+            Some(["set", "theme"]) => THEMES.iter()
+                .map(|theme| theme.to_string())
+                .chain(self.config.themes.keys().cloned())
+                .collect(),
+            Some(["import"]) | Some(["export"]) => complete_path(word),
+            _ => Vec::new(),
+        };
+
+        let matches: Vec<&String> = choices.iter()
+            .filter(|choice| choice.starts_with(word))
+            .collect();
+        let completed = match matches.as_slice() {
+            [] => return,
+            // Directories are completed into
+            [choice] if choice.ends_with('/') => choice.to_string(),
+            [choice] => format!("{} ", choice),
+            _ => {
+                self.status_line = matches.iter()
+                    .map(|choice| choice.as_str())
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                completion::common_prefix(&matches)
+            },
+        };
+
+        let text = match before {
+            Some(before) => format!("{} {}", before, completed),
+            None => completed,
+        };
+        self.command_line.input = Input::from(text);
+    }
+}
+
+/// Paths of the files and directories that start with a partial path, with
+/// directories ending in `/`
+fn complete_path(partial: &str) -> Vec<String> {
+    let (dir, prefix) = match partial.rsplit_once('/') {
+        Some((dir, _)) => (format!("{}/", dir), dir),
+        None => (String::new(), "."),
+    };
+    let dir_path = if prefix.is_empty() { "/" } else { prefix };
+
+    fs::read_dir(dir_path)
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let slash = if entry.path().is_dir() { "/" } else { "" };
+                format!("{}{}{}", dir, name, slash)
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tui_input::Input;
+
+    use crate::app::{
+        environment::Environment,
+        test_util::app,
+        App,
+    };
+
+    /// Complete a partly typed command, returning the text it's completed to
+    fn complete(app: &mut App, text: &str) -> String {
+        app.command_line.input = Input::from(text);
+        app.complete_command();
+        app.command_line.input.value().to_string()
+    }
+
+    #[test]
+    fn runs_commands_with_arguments() {
+        let mut app = app(&[]);
+        app.environments.push(Environment::new("Staging Server"));
+
+        app.run_command("new").unwrap();
+        app.run_command("rename  Pets by id ").unwrap();
+        assert_eq!(app.requests[0].name, "Pets by id");
+
+        app.run_command("env Staging Server").unwrap();
+        assert_eq!(app.env_index, Some(0));
+        app.run_command("env").unwrap();
+        assert_eq!(app.env_index, None);
+
+        app.run_command("set timeout 5").unwrap();
+        assert_eq!(app.config.timeout, Some(Duration::from_secs(5)));
+
+        app.run_command("wq").unwrap();
+        assert!(!app.is_running);
+    }
+
+    #[test]
+    fn explains_mistakes() {
+        let mut app = app(&[]);
+
+        assert_eq!(app.run_command("rename").unwrap_err(),
+            "Usage: :rename <name>");
+        assert_eq!(app.run_command("env Nowhere").unwrap_err(),
+            "Unknown environment \"Nowhere\"");
+        assert_eq!(app.run_command("set timeout").unwrap_err(),
+            "Usage: :set <setting> <value>");
+        assert!(app.run_command("set speed 1").unwrap_err()
+            .starts_with("Unknown setting \"speed\""));
+        assert_eq!(app.run_command("w").unwrap_err(), "No request to save");
+        assert_eq!(app.run_command("fly").unwrap_err(),
+            "Unknown command \"fly\"");
+        assert!(app.is_running);
+    }
+
+    #[test]
+    fn completes_commands_and_arguments() {
+        let mut app = app(&[]);
+        app.environments.push(Environment::new("staging"));
+        app.environments.push(Environment::new("stable"));
+
+        assert_eq!(complete(&mut app, "ren"), "rename ");
+        assert_eq!(complete(&mut app, "set multi"), "set multiline_height ");
+        assert_eq!(complete(&mut app, "set theme li"), "set theme light ");
+
+        // Several matches complete as far as they agree, and are listed
+        assert_eq!(complete(&mut app, "env st"), "env sta");
+        assert_eq!(app.status_line, "staging  stable");
+
+        assert_eq!(complete(&mut app, "xyz"), "xyz");
+    }
+}
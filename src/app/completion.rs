@@ -0,0 +1,45 @@
+//! # completion
+//!
+//! The `completion` crate contains helpers shared by the places text is
+//! completed with Tab, like the command line and the GraphQL query editor.
+
+/// Get the longest prefix shared by all of the given names
+pub fn common_prefix(names: &[&String]) -> String {
+    let mut prefix = match names.first() {
+        Some(name) => name.to_string(),
+        None => return String::new(),
+    };
+
+    for name in names.iter().skip(1) {
+        let len = prefix.chars()
+            .zip(name.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum();
+        prefix.truncate(len);
+    }
+
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::common_prefix;
+
+    #[test]
+    fn finds_the_prefix_shared_by_every_name() {
+        let names = [String::from("user"), String::from("users"),
+            String::from("userName")];
+
+        assert_eq!(common_prefix(&names.iter().collect::<Vec<_>>()), "user");
+        assert_eq!(common_prefix(&[&names[1]]), "users");
+        assert_eq!(common_prefix(&[]), "");
+    }
+
+    #[test]
+    fn keeps_whole_characters() {
+        let names = [String::from("café"), String::from("cafè")];
+
+        assert_eq!(common_prefix(&names.iter().collect::<Vec<_>>()), "caf");
+    }
+}
@@ -0,0 +1,805 @@
+//! # config
+//!
+//! The `config` crate reads `config.toml` from the global `~/.almagro`
+//! directory, which can remap the key of every action, and set the layout,
+//! request timeouts, default headers and how often the UI updates. Invalid
+//! settings are reported and their defaults are used instead.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs,
+    path::Path,
+    time::Duration,
+};
+
+use crossterm::event::{
+    KeyCode,
+    KeyModifiers,
+};
+use reqwest::header::{
+    HeaderName,
+    HeaderValue,
+};
+use serde::Deserialize;
+
+use super::theme::{
+    Theme,
+    ThemeFile,
+};
+
+/// Name of the config file in the global `~/.almagro` directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Settings that can be changed while the app is running
+pub const SETTINGS: [&str; 5] = [
+    "timeout",
+    "connect_timeout",
+    "list_width",
+    "multiline_height",
+    "theme",
+];
+
+/// Something a key can be bound to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    // Normal mode
+    Quit,
+    Down,
+    Up,
+    MoveDown,
+    MoveUp,
+    ScrollDown,
+    ScrollUp,
+    ScrollLeft,
+    ScrollRight,
+    Insert,
+    SwitchPanel,
+    Run,
+    NextEnvironment,
+    Close,
+    Mark,
+    Export,
+    SaveBody,
+    LoadSchema,
+    CycleChoice,
+    New,
+    Duplicate,
+    SaveTemplate,
+    Delete,
+    Undo,
+    Redo,
+    Command,
+    OpenTab,
+    NextTab,
+    PreviousTab,
+    Edit,
+    // Also in insert mode, with keys that don't type text
+    Help,
+    // Insert mode
+    Cancel,
+    Submit,
+    Newline,
+    Complete,
+}
+
+impl Action {
+    /// All actions, in the order they're described in
+    pub const ALL: [Action; 35] = [
+        Action::Quit,
+        Action::Down,
+        Action::Up,
+        Action::MoveDown,
+        Action::MoveUp,
+        Action::ScrollDown,
+        Action::ScrollUp,
+        Action::ScrollLeft,
+        Action::ScrollRight,
+        Action::Insert,
+        Action::SwitchPanel,
+        Action::Run,
+        Action::NextEnvironment,
+        Action::Close,
+        Action::Mark,
+        Action::Export,
+        Action::SaveBody,
+        Action::LoadSchema,
+        Action::CycleChoice,
+        Action::New,
+        Action::Duplicate,
+        Action::SaveTemplate,
+        Action::Delete,
+        Action::Undo,
+        Action::Redo,
+        Action::Command,
+        Action::OpenTab,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::Edit,
+        Action::Help,
+        Action::Cancel,
+        Action::Submit,
+        Action::Newline,
+        Action::Complete,
+    ];
+
+    /// Name of the action in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::MoveDown => "move_down",
+            Action::MoveUp => "move_up",
+            Action::ScrollDown => "scroll_down",
+            Action::ScrollUp => "scroll_up",
+            Action::ScrollLeft => "scroll_left",
+            Action::ScrollRight => "scroll_right",
+            Action::Insert => "insert",
+            Action::SwitchPanel => "switch_panel",
+            Action::Run => "run",
+            Action::NextEnvironment => "next_environment",
+            Action::Close => "close",
+            Action::Mark => "mark",
+            Action::Export => "export",
+            Action::SaveBody => "save_body",
+            Action::LoadSchema => "load_schema",
+            Action::CycleChoice => "cycle_choice",
+            Action::New => "new",
+            Action::Duplicate => "duplicate",
+            Action::SaveTemplate => "save_template",
+            Action::Delete => "delete",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Command => "command",
+            Action::OpenTab => "open_tab",
+            Action::NextTab => "next_tab",
+            Action::PreviousTab => "previous_tab",
+            Action::Edit => "edit",
+            Action::Help => "help",
+            Action::Cancel => "cancel",
+            Action::Submit => "submit",
+            Action::Newline => "newline",
+            Action::Complete => "complete",
+        }
+    }
+
+    /// Get the action with a name from the config file
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter()
+            .find(|action| action.name() == name)
+            .copied()
+    }
+
+    /// Is the action used in insert mode rather than normal mode?
+    pub fn is_insert(&self) -> bool {
+        matches!(self, Action::Cancel | Action::Submit | Action::Newline
+            | Action::Complete)
+    }
+
+    /// Keys the action is bound to unless the config file changes them
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q", "esc"],
+            Action::Down => &["j"],
+            Action::Up => &["k"],
+            Action::MoveDown => &["J"],
+            Action::MoveUp => &["K"],
+            Action::ScrollDown => &["down"],
+            Action::ScrollUp => &["up"],
+            Action::ScrollLeft => &["left"],
+            Action::ScrollRight => &["right"],
+            Action::Insert => &["i"],
+            Action::SwitchPanel => &["h", "l"],
+            Action::Run => &["enter"],
+            Action::NextEnvironment => &["E"],
+            Action::Close => &["c"],
+            Action::Mark => &["m"],
+            Action::Export => &["X"],
+            Action::SaveBody => &["w"],
+            Action::LoadSchema => &["S"],
+            Action::CycleChoice => &["tab"],
+            Action::New => &["n"],
+            Action::Duplicate => &["D"],
+            Action::SaveTemplate => &["T"],
+            Action::Delete => &["x"],
+            Action::Undo => &["u"],
+            Action::Redo => &["ctrl+r"],
+            Action::Command => &[":"],
+            Action::OpenTab => &["o"],
+            Action::NextTab => &["g t"],
+            Action::PreviousTab => &["g T"],
+            Action::Edit => &["e"],
+            Action::Help => &["?", "f1"],
+            Action::Cancel => &["esc"],
+            Action::Submit => &["enter"],
+            Action::Newline => &["alt+enter"],
+            Action::Complete => &["tab"],
+        }
+    }
+}
+
+/// A key, with the Ctrl and Alt modifiers held with it
+///
+/// Shift is part of the key, like `J`, so it isn't compared
+#[derive(Clone, Copy, PartialEq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Parse a key like `j`, `enter`, `ctrl+r` or `alt+enter`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut shift = false;
+        let mut key = text;
+
+        // The last part is the key, so `ctrl++` binds Ctrl and plus
+        while let Some((modifier, rest)) = key.split_once('+')
+            .filter(|(_, rest)| !rest.is_empty()) {
+
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
+                "alt" => modifiers |= KeyModifiers::ALT,
+                "shift" => shift = true,
+                _ => return Err(format!("Unknown modifier \"{}\" in \"{}\"",
+                    modifier, text)),
+            }
+            key = rest;
+        }
+
+        let code = match key.to_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            name => match (name.strip_prefix('f'), key.chars().count()) {
+                (_, 1) => {
+                    let c = key.chars().next().unwrap();
+                    KeyCode::Char(if shift { c.to_ascii_uppercase() }
+                        else { c })
+                },
+                (Some(number), _) => match number.parse::<u8>() {
+                    Ok(number) if (1..=12).contains(&number) =>
+                        KeyCode::F(number),
+                    _ => return Err(format!("Unknown key \"{}\"", text)),
+                },
+                _ => return Err(format!("Unknown key \"{}\"", text)),
+            },
+        };
+
+        Ok(Self { code, modifiers })
+    }
+
+    /// Does the key type text, so it can't be used in insert mode?
+    pub fn is_text(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self.modifiers.intersects(KeyModifiers::CONTROL
+                | KeyModifiers::ALT)
+    }
+
+    /// The key pressed, from a key event
+    pub fn pressed(modifiers: KeyModifiers, code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(number) => write!(f, "f{}", number),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+/// Keys pressed one after another, like `g t`, or a single key
+#[derive(Clone, PartialEq)]
+pub struct KeyBinding {
+    keys: Vec<Key>,
+}
+
+impl KeyBinding {
+    /// Parse keys separated by spaces, like `g t` or `ctrl+r`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let keys = text.split_whitespace()
+            .map(Key::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Err(format!("Unknown key \"{}\"", text));
+        }
+
+        Ok(Self { keys })
+    }
+
+    /// Does any of the keys type text, so it can't be used in insert mode?
+    pub fn is_text(&self) -> bool {
+        self.keys.iter().any(|key| key.is_text())
+    }
+
+    /// Do the keys start with the keys pressed, or are they the same?
+    fn starts_with(&self, pressed: &[Key]) -> bool {
+        self.keys.starts_with(pressed)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, key) in self.keys.iter().enumerate() {
+            if index > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", key)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What the keys pressed so far are bound to
+pub enum KeyMatch {
+    Action(Action),
+    // The keys start a longer binding, so the next key is waited for
+    Pending,
+    None,
+}
+
+/// The keys each action is bound to
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyBinding>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL.iter()
+                .map(|action| (*action, action.default_keys().iter()
+                    .filter_map(|key| KeyBinding::parse(key).ok())
+                    .collect()))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Get the action a key is bound to in insert or normal mode, on its
+    /// own rather than after other keys
+    pub fn action(&self, insert: bool, modifiers: KeyModifiers,
+        code: KeyCode) -> Option<Action> {
+
+        match self.lookup(insert, &[], Key::pressed(modifiers, code)) {
+            KeyMatch::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    /// Get what a key is bound to in insert or normal mode, after the keys
+    /// pending before it
+    ///
+    /// Help can be opened in insert mode too, by its keys that don't type
+    /// text
+    pub fn lookup(&self, insert: bool, pending: &[Key], key: Key)
+        -> KeyMatch {
+
+        let mut pressed = pending.to_vec();
+        pressed.push(key);
+
+        let bound: Vec<(Action, &KeyBinding)> = self.bindings.iter()
+            .filter(|(action, _)| action.is_insert() == insert
+                || (insert && *action == Action::Help))
+            .flat_map(|(action, keys)| keys.iter()
+                .filter(|keys| action.is_insert() == insert || !keys.is_text())
+                .map(move |keys| (*action, keys)))
+            .filter(|(_, keys)| keys.starts_with(&pressed))
+            .collect();
+
+        match bound.iter().find(|(_, keys)| keys.keys == pressed) {
+            Some((action, _)) => KeyMatch::Action(*action),
+            None if bound.is_empty() => KeyMatch::None,
+            None => KeyMatch::Pending,
+        }
+    }
+
+    /// Get the keys an action is bound to
+    pub fn keys(&self, action: Action) -> &[KeyBinding] {
+        self.bindings.iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    /// Bind an action to keys instead of its current ones
+    fn bind(&mut self, action: Action, keys: Vec<KeyBinding>) {
+        if let Some((_, bound)) = self.bindings.iter_mut()
+            .find(|(bound, _)| *bound == action) {
+
+            *bound = keys;
+        }
+    }
+
+    /// Describe keys bound to more than one action in the same mode
+    fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+
+        for (i, (action, keys)) in self.bindings.iter().enumerate() {
+            for (other, other_keys) in self.bindings[i + 1..].iter() {
+                if action.is_insert() != other.is_insert() {
+                    continue;
+                }
+
+                for key in keys.iter() {
+                    for other_key in other_keys.iter() {
+                        if key == other_key {
+                            conflicts.push(format!("\"{}\" is bound to both \
+                                {} and {}", key, action.name(), other.name()));
+                        } else if other_key.starts_with(&key.keys) {
+                            conflicts.push(prefix_conflict(key, *action,
+                                other_key, *other));
+                        } else if key.starts_with(&other_key.keys) {
+                            conflicts.push(prefix_conflict(other_key, *other,
+                                key, *action));
+                        }
+                    }
+                }
+            }
+        }
+
+        conflicts
+    }
+}
+
+/// Describe keys that can't be pressed because the start of them is bound
+/// to another action
+fn prefix_conflict(short: &KeyBinding, short_action: Action,
+    long: &KeyBinding, long_action: Action) -> String {
+
+    format!("\"{}\" is bound to {}, so \"{}\" for {} can't be pressed",
+        short, short_action.name(), long, long_action.name())
+}
+
+/// Settings read from the config file
+pub struct Config {
+    pub keys: Keymap,
+    // Percentage of the width taken by the request list
+    pub list_width: u16,
+    // Percentage of the height taken by each multiline field
+    pub multiline_height: u16,
+    // How long to wait for the headers of a response, and to connect
+    pub timeout: Option<Duration>,
+    pub connect_timeout: Duration,
+    // Headers sent with every request that doesn't set them itself
+    pub headers: Vec<(String, String)>,
+    // How often the UI updates without key presses
+    pub tick_delay: Duration,
+    // Colours the UI is drawn with, and the themes defined in the file
+    pub theme: Theme,
+    pub themes: BTreeMap<String, ThemeFile>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            keys: Keymap::default(),
+            list_width: 20,
+            multiline_height: 20,
+            // Bodies, like event streams, are read without a timeout
+            timeout: Some(Duration::from_secs(30)),
+            connect_timeout: Duration::from_secs(30),
+            headers: Vec::new(),
+            tick_delay: Duration::from_millis(250),
+            theme: Theme::default(),
+            themes: BTreeMap::new(),
+        }
+    }
+}
+
+/// The contents of the config file, before they're checked
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    keys: BTreeMap<String, KeyList>,
+    layout: LayoutFile,
+    // Seconds
+    timeout: Option<u64>,
+    connect_timeout: Option<u64>,
+    headers: BTreeMap<String, String>,
+    // Milliseconds
+    tick_delay: Option<u64>,
+    // A built in theme, or one defined under `[themes]`
+    theme: Option<String>,
+    themes: BTreeMap<String, ThemeFile>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct LayoutFile {
+    list_width: Option<u16>,
+    multiline_height: Option<u16>,
+}
+
+/// One key, or a list of keys
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Config {
+    /// Read the config file in a directory, if there is one
+    ///
+    /// Returns the config, and a description of each invalid setting, which
+    /// is left at its default. Colours are turned off if `NO_COLOR` is set
+    pub fn load(dir: &Path) -> (Self, Vec<String>) {
+        let path = dir.join(CONFIG_FILE);
+        let (mut config, errors) = match fs::read_to_string(&path) {
+            Ok(text) => match toml::from_str::<ConfigFile>(&text) {
+                Ok(file) => Self::from_file(file),
+                Err(e) => (Self::default(), vec![format!("{}", e)]),
+            },
+            Err(_) => (Self::default(), Vec::new()),
+        };
+
+        config.theme = config.theme.honor_no_color();
+        (config, errors)
+    }
+
+    /// Check the settings in a config file, using the default for each
+    /// invalid one
+    fn from_file(file: ConfigFile) -> (Self, Vec<String>) {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+
+        for (name, keys) in file.keys {
+            let action = match Action::from_name(&name) {
+                Some(action) => action,
+                None => {
+                    errors.push(format!("Unknown action \"{}\"", name));
+                    continue;
+                },
+            };
+
+            let keys = match keys {
+                KeyList::One(key) => vec![key],
+                KeyList::Many(keys) => keys,
+            };
+            match keys.iter()
+                .map(|key| KeyBinding::parse(key))
+                .collect::<Result<Vec<_>, _>>() {
+
+                Ok(keys) => config.keys.bind(action, keys),
+                Err(e) => errors.push(e),
+            }
+        }
+        errors.extend(config.keys.conflicts());
+
+        config.themes = file.themes;
+        let settings = [
+            ("layout.list_width", file.layout.list_width.map(u64::from)),
+            ("layout.multiline_height",
+                file.layout.multiline_height.map(u64::from)),
+            ("timeout", file.timeout),
+            ("connect_timeout", file.connect_timeout),
+        ];
+        for (name, value) in settings {
+            if let Some(value) = value {
+                let name = name.trim_start_matches("layout.");
+                if let Err(e) = config.set(name, &value.to_string()) {
+                    errors.push(e);
+                }
+            }
+        }
+        if let Some(theme) = file.theme {
+            if let Err(e) = config.set("theme", &theme) {
+                errors.push(e);
+            }
+        }
+
+        match file.tick_delay {
+            Some(millis) if !(10..=5000).contains(&millis) => errors.push(
+                String::from("tick_delay must be from 10 to 5000 \
+                    milliseconds")),
+            Some(millis) => config.tick_delay = Duration::from_millis(millis),
+            None => (),
+        }
+
+        for (name, value) in file.headers {
+            if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                errors.push(format!("\"{}\" isn't a valid header name", name));
+            } else if HeaderValue::from_str(&value).is_err() {
+                errors.push(format!("The value of the {} header isn't valid",
+                    name));
+            } else {
+                config.headers.push((name, value));
+            }
+        }
+
+        (config, errors)
+    }
+
+    /// Change a setting from its text, like the value of a `:set` command
+    ///
+    /// `timeout` can be `off` so requests aren't timed out
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let number = |min: u64, max: u64, unit: &str| value.parse::<u64>()
+            .ok()
+            .filter(|number| (min..=max).contains(number))
+            .ok_or(format!("{} must be from {} to {} {}", name, min, max,
+                unit));
+
+        match name {
+            "timeout" if value == "off" => self.timeout = None,
+            "timeout" => self.timeout = Some(Duration::from_secs(
+                number(1, 86400, "seconds")?)),
+            "connect_timeout" => self.connect_timeout = Duration::from_secs(
+                number(1, 3600, "seconds")?),
+            "list_width" => self.list_width = number(10, 90, "percent")?
+                as u16,
+            "multiline_height" => self.multiline_height =
+                number(5, 80, "percent")? as u16,
+            "theme" => self.theme = Theme::build(value, &self.themes)?
+                .honor_no_color(),
+            _ => return Err(format!("Unknown setting \"{}\"", name)),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crossterm::event::{
+        KeyCode,
+        KeyModifiers,
+    };
+    use serde_json::json;
+
+    use super::{
+        Action,
+        Config,
+        ConfigFile,
+        Key,
+        KeyBinding,
+        KeyMatch,
+        Keymap,
+    };
+
+    /// Check the settings of a config file, written as JSON
+    fn from_file(file: serde_json::Value) -> (Config, Vec<String>) {
+        Config::from_file(serde_json::from_value::<ConfigFile>(file).unwrap())
+    }
+
+    fn key(c: char) -> Key {
+        Key::pressed(KeyModifiers::NONE, KeyCode::Char(c))
+    }
+
+    #[test]
+    fn parses_keys() {
+        let ctrl_r = Key::pressed(KeyModifiers::CONTROL, KeyCode::Char('r'));
+
+        assert!(Key::parse("ctrl+r").unwrap() == ctrl_r);
+        assert!(Key::parse("Ctrl+r").unwrap() == ctrl_r);
+        assert!(Key::parse("shift+j").unwrap() == key('J'));
+        assert!(Key::parse("space").unwrap() == key(' '));
+        assert!(Key::parse("f5").unwrap()
+            == Key::pressed(KeyModifiers::NONE, KeyCode::F(5)));
+        assert!(Key::parse("ctrl++").unwrap()
+            == Key::pressed(KeyModifiers::CONTROL, KeyCode::Char('+')));
+
+        for text in ["hyper+x", "f13", "enterr"] {
+            assert!(Key::parse(text).is_err(), "{} was parsed", text);
+        }
+        assert_eq!(KeyBinding::parse("g  ctrl+t").unwrap().to_string(),
+            "g ctrl+t");
+        assert!(KeyBinding::parse(" ").is_err());
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_binding() {
+        let keymap = Keymap::default();
+
+        assert!(matches!(keymap.lookup(false, &[], key('g')),
+            KeyMatch::Pending));
+        assert!(matches!(keymap.lookup(false, &[key('g')], key('t')),
+            KeyMatch::Action(Action::NextTab)));
+        assert!(matches!(keymap.lookup(false, &[key('g')], key('x')),
+            KeyMatch::None));
+        assert!(matches!(keymap.lookup(false, &[], key('j')),
+            KeyMatch::Action(Action::Down)));
+    }
+
+    #[test]
+    fn only_binds_text_keys_in_normal_mode() {
+        let keymap = Keymap::default();
+        let f1 = Key::pressed(KeyModifiers::NONE, KeyCode::F(1));
+
+        assert!(matches!(keymap.lookup(true, &[], key('j')), KeyMatch::None));
+        assert!(matches!(keymap.lookup(true, &[], key('?')), KeyMatch::None));
+        assert!(matches!(keymap.lookup(true, &[], f1),
+            KeyMatch::Action(Action::Help)));
+    }
+
+    #[test]
+    fn reports_conflicting_keys() {
+        let (_, errors) = from_file(json!({ "keys": { "run": "j" } }));
+        assert_eq!(errors, ["\"j\" is bound to both down and run"]);
+
+        let (_, errors) = from_file(json!({ "keys": { "new": "g" } }));
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("\"g\" is bound to new, so \"g t\""));
+
+        let (config, errors) = from_file(json!({
+            "keys": { "run": ["ctrl+s", "enter"], "quit": "q" },
+        }));
+        assert!(errors.is_empty());
+        assert_eq!(config.keys.keys(Action::Run).len(), 2);
+        assert!(config.keys.keys(Action::Quit)
+            == [KeyBinding::parse("q").unwrap()]);
+    }
+
+    #[test]
+    fn checks_settings() {
+        assert_eq!(Config::default().timeout, Some(Duration::from_secs(30)));
+
+        let (config, errors) = from_file(json!({
+            "timeout": 5,
+            "layout": { "list_width": 200 },
+            "headers": { "Bad Header": "x" },
+            "keys": { "fly": "f" },
+        }));
+        assert_eq!(config.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(config.list_width, 20);
+        assert_eq!(errors, [
+            "Unknown action \"fly\"",
+            "list_width must be from 10 to 90 percent",
+            "\"Bad Header\" isn't a valid header name",
+        ]);
+
+        let mut config = Config::default();
+        config.set("timeout", "off").unwrap();
+        assert_eq!(config.timeout, None);
+        assert!(config.set("timeout", "0").is_err());
+        assert!(config.set("speed", "1").is_err());
+    }
+}
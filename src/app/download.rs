@@ -0,0 +1,268 @@
+//! # download
+//!
+//! The `download` crate contains structures and methods for streaming response
+//! bodies on their own thread. The full body is written to a temporary file
+//! so it can be saved, and only a capped preview is kept in memory.
+
+use std::{
+    env,
+    fs::{
+        self,
+        File,
+        OpenOptions,
+    },
+    io::{
+        self,
+        Read,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    process,
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        mpsc,
+    },
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use reqwest::blocking;
+
+/// Most bytes of a body kept in memory and shown in the response panel
+pub const PREVIEW_LEN: usize = 256 * 1024;
+
+// Size of the chunks bodies are read in
+const CHUNK_LEN: usize = 16 * 1024;
+
+/// Most bytes of a binary body shown in its hexdump
+pub const HEXDUMP_LEN: usize = 16 * 1024;
+
+// Used to give each temporary body file a unique name
+static BODY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary file holding a full response body, removed when dropped
+pub struct BodyFile {
+    pub path: PathBuf,
+}
+
+impl BodyFile {
+    fn create() -> io::Result<(Self, File)> {
+        let path = env::temp_dir().join(format!("almagro-{}-{}.body",
+            process::id(), BODY_COUNT.fetch_add(1, Ordering::Relaxed)));
+        // The name is predictable, so never open a file that's already there
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        Ok((Self { path }, file))
+    }
+
+    /// Copy the body to a new file, failing if the file already exists
+    pub fn save_to(&self, path: &Path) -> io::Result<u64> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        io::copy(&mut File::open(&self.path)?, &mut file)
+    }
+}
+
+impl Drop for BodyFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+/// Messages from the download thread to the UI
+enum DownloadEvent {
+    // Bytes for the preview, and the total bytes read so far
+    Chunk(Vec<u8>, u64),
+    // Whether the body was read, and how long reading it took
+    Done(Result<(), String>, Duration),
+}
+
+/// A response body being streamed
+pub struct Download {
+    events: mpsc::Receiver<DownloadEvent>,
+    // Total size of the body, if the server sent it
+    pub total: Option<u64>,
+    pub read: u64,
+    pub preview: Vec<u8>,
+    pub body_file: BodyFile,
+    // The error that stopped the download, if any
+    pub error: Option<String>,
+    pub is_done: bool,
+    // How long reading the body took, once it's done
+    pub duration: Duration,
+}
+
+impl Download {
+    /// Start reading a response body on a new thread
+    pub fn start(resp: blocking::Response)
+        -> Result<Self, Box<dyn std::error::Error>> {
+
+        let (body_file, file) = BodyFile::create()?;
+        let (sender, events) = mpsc::channel();
+        let total = resp.content_length();
+
+        thread::spawn(move || {
+            let start = Instant::now();
+            let result = read_body(resp, file, &sender)
+                .map_err(|e| format!("{}", e));
+            sender.send(DownloadEvent::Done(result, start.elapsed())).ok();
+        });
+
+        Ok(Self {
+            events,
+            total,
+            read: 0,
+            preview: Vec::new(),
+            body_file,
+            error: None,
+            is_done: false,
+            duration: Duration::ZERO,
+        })
+    }
+
+    /// Move chunks from the download thread into the preview
+    ///
+    /// Returns whether anything changed
+    pub fn update(&mut self) -> bool {
+        let mut updated = false;
+
+        while let Ok(event) = self.events.try_recv() {
+            updated = true;
+            match event {
+                DownloadEvent::Chunk(bytes, read) => {
+                    self.preview.extend(bytes);
+                    self.read = read;
+                },
+                DownloadEvent::Done(result, duration) => {
+                    self.error = result.err();
+                    self.is_done = true;
+                    self.duration = duration;
+                },
+            }
+        }
+
+        updated
+    }
+
+    /// Describe how much of the body has been read
+    pub fn progress(&self) -> String {
+        match self.total {
+            Some(total) if !self.is_done => format!("{} / {}",
+                format_size(self.read), format_size(total)),
+            _ => format_size(self.read),
+        }
+    }
+
+    /// Text shown in the response panel for the body
+    ///
+    /// Binary bodies are shown as a hexdump
+    pub fn preview_text(&self) -> String {
+        let mut text = if is_binary(&self.preview) {
+            hexdump(&self.preview[..self.preview.len().min(HEXDUMP_LEN)])
+        } else {
+            String::from_utf8_lossy(&self.preview).into_owned()
+        };
+
+        if self.read > self.preview.len() as u64 {
+            text.push_str(&format!("\n\n[Showing the start of a {} body, press \
+                w to save all of it to a file]", format_size(self.read)));
+        }
+
+        if let Some(e) = &self.error {
+            text.push_str(&format!("\n\n[Download failed: {}]", e));
+        }
+
+        text
+    }
+}
+
+/// Read the body into the temporary file, sending the first `PREVIEW_LEN`
+/// bytes back for the preview
+fn read_body(mut resp: blocking::Response, mut file: File,
+    sender: &mpsc::Sender<DownloadEvent>)
+    -> Result<(), Box<dyn std::error::Error>> {
+
+    let mut buf = vec![0; CHUNK_LEN];
+    let mut read: u64 = 0;
+
+    loop {
+        let len = resp.read(&mut buf)?;
+        if len == 0 {
+            return Ok(());
+        }
+
+        file.write_all(&buf[..len])?;
+
+        let preview_len = PREVIEW_LEN.saturating_sub(read as usize).min(len);
+        read += len as u64;
+        sender.send(DownloadEvent::Chunk(buf[..preview_len].to_vec(), read))?;
+    }
+}
+
+/// Does the body look like binary data rather than text?
+pub fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return true;
+    }
+
+    // The preview may end part way through a character, which is fine
+    match std::str::from_utf8(bytes) {
+        Ok(_) => false,
+        Err(e) => e.error_len().is_some(),
+    }
+}
+
+/// Format bytes as lines of offset, hex bytes and printable characters, like
+/// `hexdump -C`
+pub fn hexdump(bytes: &[u8]) -> String {
+    bytes.chunks(16)
+        .enumerate()
+        .map(|(line, chunk)| {
+            let hex: Vec<String> = chunk.iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            let chars: String = chunk.iter()
+                .map(|byte| if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                })
+                .collect();
+
+            format!("{:08x}  {:<47}  |{}|", line * 16, hex.join(" "), chars)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format a number of bytes with a binary unit
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
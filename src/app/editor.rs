@@ -0,0 +1,340 @@
+//! # editor
+//!
+//! `editor` opens the selected field, or the whole request as JSON, in the
+//! editor named by `$VISUAL` or `$EDITOR`. The UI hands the terminal over
+//! while the editor runs, and the text is read back and saved once it's
+//! closed.
+
+use std::{
+    env,
+    fs::{
+        self,
+        OpenOptions,
+    },
+    io::{
+        ErrorKind,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::{
+        self,
+        Command,
+    },
+};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+use serde_json::Value;
+
+use super::{
+    history::{
+        Change,
+        Snapshot,
+    },
+    request::{
+        BodyMode,
+        ReqField,
+        Request,
+    },
+    storage::UNTRACKED_FIELDS,
+    App,
+    SelElement,
+};
+
+/// Editor used when neither `$VISUAL` nor `$EDITOR` is set
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Most temporary file names tried before giving up
+const MAX_TEMP_ATTEMPTS: usize = 100;
+
+/// What is opened in the editor
+#[derive(Clone, Copy)]
+pub enum EditTarget {
+    // A field of the selected request
+    Field(ReqField),
+    // All the saved fields of the selected request, as JSON
+    Request,
+}
+
+impl App {
+    /// Ask for the selected field, or the selected request in the request
+    /// list, to be opened in the editor before the UI is drawn again
+    pub fn open_editor(&mut self) {
+        if self.requests.is_empty() {
+            return;
+        }
+
+        self.edit_target = Some(match self.sel_element {
+            SelElement::LIST => EditTarget::Request,
+            SelElement::INFOPANEL =>
+                EditTarget::Field(self.fields[self.input_index]),
+        });
+    }
+
+    /// Open a field or the selected request in the editor, and save the
+    /// text it's left with
+    ///
+    /// Blocks until the editor is closed. Errors are shown in the status
+    /// line, and leave the request as it was
+    pub fn edit(&mut self, target: EditTarget) {
+        if let Err(e) = self.try_edit(target) {
+            self.status_line = e;
+        }
+    }
+
+    /// Open a field or the selected request in the editor, and save the
+    /// text it's left with
+    fn try_edit(&mut self, target: EditTarget) -> Result<(), String> {
+        let cur_req = self.requests.get(self.req_index)
+            .ok_or("No request to edit")?;
+
+        let text = match target {
+            EditTarget::Field(field) => cur_req.field_text(field),
+            EditTarget::Request => request_json(cur_req)?,
+        };
+        let path = create_temp_file(extension(target, cur_req), &text)?;
+
+        let edited = run_editor(&path);
+        fs::remove_file(&path).ok();
+        let edited = edited?;
+        if edited == text {
+            return Ok(());
+        }
+
+        match target {
+            EditTarget::Field(field) => self.set_edited_field(field, &text,
+                &edited)?,
+            EditTarget::Request => self.set_edited_request(&edited)?,
+        }
+
+        self.update_inputs();
+        self.save_current_request();
+        Ok(())
+    }
+
+    /// Set a field of the selected request to the text left in the editor
+    fn set_edited_field(&mut self, field: ReqField, before: &str,
+        edited: &str) -> Result<(), String> {
+
+        // Editors end files with a newline, which the field didn't have
+        let edited = if before.ends_with('\n') {
+            edited
+        } else {
+            edited.strip_suffix('\n')
+                .map(|text| text.strip_suffix('\r').unwrap_or(text))
+                .unwrap_or(edited)
+        };
+
+        let cur_req = &mut self.requests[self.req_index];
+        if !field.is_multiline() && edited.contains('\n') {
+            return Err(format!("{} can't have more than one line",
+                field.title(cur_req.body_mode)));
+        }
+        cur_req.set_field_text(field, edited);
+        let after = cur_req.field_text(field);
+
+        if field == ReqField::Method
+            && !edited.trim().eq_ignore_ascii_case(&after) {
+
+            return Err(format!("\"{}\" isn't a valid method", edited));
+        }
+        if after == before {
+            return Ok(());
+        }
+
+        self.history.record(Change::Edit {
+            index: self.req_index,
+            field,
+            before: before.to_string(),
+            after,
+        });
+        Ok(())
+    }
+
+    /// Set the saved fields of the selected request to the JSON left in the
+    /// editor
+    fn set_edited_request(&mut self, edited: &str) -> Result<(), String> {
+        let cur_req = &mut self.requests[self.req_index];
+
+        // Fields left out of the JSON are kept as they were
+        let mut value = serde_json::to_value(&*cur_req)
+            .map_err(|e| format!("{}", e))?;
+        let edited: Value = serde_json::from_str(edited)
+            .map_err(|e| format!("The request isn't valid JSON: {}", e))?;
+        match (&mut value, edited) {
+            (Value::Object(fields), Value::Object(edited)) => fields.extend(
+                edited.into_iter()
+                    .filter(|(name, _)| !UNTRACKED_FIELDS.contains(
+                        &name.as_str()))),
+            _ => return Err(String::from("The request must be a JSON \
+                object")),
+        }
+        let edited: Request = serde_json::from_value(value)
+            .map_err(|e| format!("The request isn't valid: {}", e))?;
+
+        let before = Snapshot::of(cur_req);
+        cur_req.set_saved_fields(edited);
+        self.history.record(Change::Replace {
+            index: self.req_index,
+            before,
+            after: Snapshot::of(cur_req),
+        });
+        Ok(())
+    }
+}
+
+/// The saved fields of a request as JSON, without the ones that aren't
+/// kept in request files
+fn request_json(req: &Request) -> Result<String, String> {
+    let mut value = serde_json::to_value(req)
+        .map_err(|e| format!("{}", e))?;
+    if let Value::Object(fields) = &mut value {
+        for field in UNTRACKED_FIELDS {
+            fields.remove(field);
+        }
+    }
+
+    serde_json::to_string_pretty(&value)
+        .map(|json| json + "\n")
+        .map_err(|e| format!("{}", e))
+}
+
+/// Extension of the file opened in the editor, so it can highlight the text
+fn extension(target: EditTarget, req: &Request) -> &'static str {
+    match target {
+        EditTarget::Request => "json",
+        EditTarget::Field(ReqField::Body) => match req.body_mode {
+            BodyMode::GraphQl => "graphql",
+            BodyMode::Raw if req.content_type.contains("json") => "json",
+            BodyMode::Raw if req.content_type.contains("xml") => "xml",
+            BodyMode::Raw if req.content_type.contains("html") => "html",
+            BodyMode::Raw if req.content_type.contains("javascript") => "js",
+            _ => "txt",
+        },
+        EditTarget::Field(ReqField::Variables)
+            | EditTarget::Field(ReqField::ResponseSchema) => "json",
+        EditTarget::Field(_) => "txt",
+    }
+}
+
+/// Write text to a new temporary file that only the user can read
+///
+/// Files that already exist are never opened, so the file can't be swapped
+/// for a link to somewhere else
+fn create_temp_file(extension: &str, text: &str) -> Result<PathBuf, String> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    for attempt in 0..MAX_TEMP_ATTEMPTS {
+        let path = env::temp_dir().join(format!("almagro-{}-{}.{}",
+            process::id(), attempt, extension));
+
+        match options.open(&path) {
+            Ok(mut file) => {
+                return file.write_all(text.as_bytes())
+                    .map(|_| path.clone())
+                    .map_err(|e| {
+                        fs::remove_file(&path).ok();
+                        format!("Couldn't write {}: {}", path.display(), e)
+                    });
+            },
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Couldn't create {}: {}",
+                path.display(), e)),
+        }
+    }
+
+    Err(String::from("Couldn't create a temporary file to edit"))
+}
+
+/// Open a file in the editor, and read it back once the editor is closed
+///
+/// The editor can be a command with arguments, like `code --wait`
+fn run_editor(path: &Path) -> Result<String, String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(DEFAULT_EDITOR);
+
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Couldn't run {}: {}", editor, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}, so nothing was changed",
+            editor, status));
+    }
+
+    fs::read_to_string(path)
+        .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        fs,
+        process,
+    };
+
+    use super::{
+        create_temp_file,
+        MAX_TEMP_ATTEMPTS,
+    };
+
+    #[test]
+    fn creates_new_files() {
+        let first = create_temp_file("txt", "one").unwrap();
+        let second = create_temp_file("txt", "two").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "one");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "two");
+
+        fs::remove_file(first).ok();
+        fs::remove_file(second).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_files_private_and_ignores_links() {
+        use std::os::unix::fs::{
+            symlink,
+            PermissionsExt,
+        };
+
+        // Links planted at every name the file could get
+        let target = env::temp_dir()
+            .join(format!("almagro-target-{}", process::id()));
+        fs::write(&target, "target").unwrap();
+        let links: Vec<_> = (0..MAX_TEMP_ATTEMPTS)
+            .map(|attempt| env::temp_dir().join(format!("almagro-{}-{}.link",
+                process::id(), attempt)))
+            .collect();
+        for link in links.iter() {
+            symlink(&target, link).unwrap();
+        }
+
+        assert!(create_temp_file("link", "text").is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "target");
+        for link in links.iter() {
+            fs::remove_file(link).ok();
+        }
+
+        let path = create_temp_file("link", "text").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_file(path).ok();
+        fs::remove_file(target).ok();
+    }
+}
@@ -0,0 +1,66 @@
+//! # environment
+//!
+//! The `environment` crate contains named sets of variables, and methods to
+//! substitute them for the `{{name}}` placeholders in requests.
+
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// Variable names and their values
+pub type Variables = BTreeMap<String, String>;
+
+/// A named set of variables, like the base url of a deployment
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Environment {
+    pub name: String,
+    pub variables: Variables,
+    pub db_id: String, // Id used to access environment in JSON database
+}
+
+impl Environment {
+    /// Create a new environment with no variables
+    ///
+    /// Takes in a name for the environment
+    pub fn new<T: ToString>(name: T) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Replace each `{{name}}` in text with the value of the variable called
+/// `name`
+///
+/// Placeholders for unknown variables are left as they are
+pub fn substitute(text: &str, variables: &Variables) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+
+        match after.find("}}") {
+            Some(end) => {
+                let name = after[..end].trim();
+                match variables.get(name) {
+                    Some(value) => result.push_str(value),
+                    None => result.push_str(&rest[start..start + end + 4]),
+                }
+                rest = &after[end + 2..];
+            },
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            },
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
@@ -0,0 +1,67 @@
+//! # export
+//!
+//! `export` implements methods to write requests and their last responses to
+//! files other tools can open.
+
+use std::{
+    fs::OpenOptions,
+    io::{
+        ErrorKind,
+        Write,
+    },
+    path::Path,
+};
+
+use super::{
+    har,
+    App,
+};
+
+/// File HAR exports are written to, in the current directory
+pub const HAR_EXPORT_PATH: &str = "almagro.har";
+
+impl App {
+    /// Write the marked requests, or the selected request if none are marked,
+    /// to a HAR file with their last responses and timings
+    ///
+    /// Files that already exist aren't overwritten. Returns the number of
+    /// requests exported
+    pub fn export_har(&self, path: &Path)
+        -> Result<usize, Box<dyn std::error::Error>> {
+
+        let mut indices: Vec<usize> = (0..self.requests.len())
+            .filter(|index| self.requests[*index].marked)
+            .collect();
+        if indices.is_empty() && self.req_index < self.requests.len() {
+            indices.push(self.req_index);
+        }
+
+        // WebSocket sessions don't fit in HAR entries
+        let entries: Vec<_> = indices.into_iter()
+            .filter(|index| !self.requests[*index].is_websocket())
+            .map(|index| {
+                let req = &self.requests[index];
+                har::entry(req, &req.resolve(&self.request_variables(index)))
+            })
+            .collect();
+        if entries.is_empty() {
+            return Err("There are no HTTP requests to export".into());
+        }
+
+        let count = entries.len();
+        let json = serde_json::to_string_pretty(&har::document(entries))?;
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|e| match e.kind() {
+                ErrorKind::AlreadyExists => format!("{} already exists, \
+                    export to another file with :export <file>",
+                    path.display()),
+                _ => format!("{}", e),
+            })?;
+        file.write_all(json.as_bytes())?;
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,114 @@
+//! # graphql
+//!
+//! The `graphql` crate contains the body sent by GraphQL requests, and methods
+//! to introspect a GraphQL schema for query completion.
+
+use std::time::Duration;
+
+use reqwest::blocking;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    json,
+    Value,
+};
+
+use super::request::send;
+
+/// Query asking a server for the names of its types and their fields
+const INTROSPECTION_QUERY: &str =
+    "query IntrospectionQuery { __schema { types { name fields { name } } } }";
+
+/// Contains the parts of a GraphQL request body
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct GraphQlBody {
+    pub query: String,
+    // JSON object with the values of the query's variables
+    pub variables: String,
+    pub operation_name: String,
+}
+
+impl GraphQlBody {
+    /// Build the standard `{query, variables, operationName}` JSON body
+    pub fn to_json(&self) -> Result<Value, serde_json::Error> {
+        let variables = if self.variables.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&self.variables)?
+        };
+
+        let operation_name = if self.operation_name.trim().is_empty() {
+            Value::Null
+        } else {
+            Value::String(self.operation_name.trim().to_string())
+        };
+
+        Ok(json!({
+            "query": self.query,
+            "variables": variables,
+            "operationName": operation_name,
+        }))
+    }
+}
+
+/// Run an introspection query against a GraphQL endpoint
+///
+/// Takes in a request builder with the url and headers to send, waiting at
+/// most `timeout` for the response. Returns the sorted names of every type
+/// and field in the schema, skipping the built in `__` introspection types
+pub fn introspect(builder: blocking::RequestBuilder, timeout: Option<Duration>)
+    -> Result<Vec<String>, Box<dyn std::error::Error>> {
+
+    let builder = builder.json(&json!({ "query": INTROSPECTION_QUERY }));
+    let resp: Value = send(builder, timeout)?
+        .error_for_status()?
+        .json()?;
+
+    let types = resp["data"]["__schema"]["types"].as_array()
+        .ok_or("Response doesn't contain a GraphQL schema")?;
+
+    let mut names = Vec::new();
+    for gql_type in types {
+        if let Some(name) = gql_type["name"].as_str() {
+            names.push(name.to_string());
+        }
+
+        if let Some(fields) = gql_type["fields"].as_array() {
+            names.extend(fields.iter()
+                .filter_map(|field| field["name"].as_str())
+                .map(|name| name.to_string()));
+        }
+    }
+
+    names.retain(|name| !name.starts_with("__"));
+    names.sort();
+    names.dedup();
+
+    Ok(names)
+}
+
+/// Get the completions for the word that ends at `cursor` in `text`
+///
+/// Returns the start of the word (as a char index) and the names that begin
+/// with it
+pub fn complete<'a>(text: &str, cursor: usize, names: &'a [String])
+    -> (usize, Vec<&'a String>) {
+
+    let chars: Vec<char> = text.chars().take(cursor).collect();
+    let start = chars.iter()
+        .rposition(|c| !(c.is_alphanumeric() || *c == '_'))
+        .map_or(0, |pos| pos + 1);
+    let word: String = chars[start..].iter().collect();
+
+    if word.is_empty() {
+        return (start, Vec::new());
+    }
+
+    let matches = names.iter()
+        .filter(|name| name.starts_with(&word) && **name != word)
+        .collect();
+
+    (start, matches)
+}
@@ -0,0 +1,474 @@
+//! # har
+//!
+//! The `har` crate contains methods to turn the entries of HAR files, as saved
+//! by browser devtools and proxies, into requests, and to write requests and
+//! their last responses as HAR 1.2 entries.
+
+use std::{
+    fs,
+    time::{
+        Duration,
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use reqwest::StatusCode;
+use serde_json::{
+    json,
+    Value,
+};
+
+use super::{
+    download::{
+        hexdump,
+        is_binary,
+        HEXDUMP_LEN,
+    },
+    import::Collection,
+    request::{
+        BodyMode,
+        FormField,
+        ReqField,
+        Request,
+    },
+};
+
+/// Version of the HAR format written by exports
+const HAR_VERSION: &str = "1.2";
+
+/// Is the document a HAR file?
+pub fn is_har(doc: &Value) -> bool {
+    doc["log"]["entries"].is_array()
+}
+
+/// Turn the entries of a HAR file into requests in a folder
+///
+/// Captured responses are kept as the requests' last responses
+pub fn to_collection(har: &Value, folder: &str)
+    -> Result<Collection, Box<dyn std::error::Error>> {
+
+    let entries = har["log"]["entries"].as_array()
+        .ok_or("The HAR file doesn't have any entries")?;
+
+    let requests = entries.iter()
+        .filter_map(|entry| {
+            let mut req = entry_request(&entry["request"])?;
+            req.folder = folder.to_string();
+
+            let status = entry["response"]["status"].as_u64().unwrap_or(0);
+            if status > 0 {
+                req.status = status.to_string();
+                req.resp = content_text(&entry["response"]["content"]);
+            }
+
+            Some(req)
+        })
+        .collect();
+
+    Ok(Collection {
+        requests,
+        environments: Vec::new(),
+    })
+}
+
+/// The text of a captured response body
+///
+/// Bodies encoded as base64 are decoded, and shown as a hexdump if they're
+/// binary
+fn content_text(content: &Value) -> String {
+    let text = content["text"].as_str().unwrap_or_default();
+    if content["encoding"] != "base64" {
+        return text.to_string();
+    }
+
+    match base64::decode(text.trim()) {
+        Ok(bytes) if is_binary(&bytes) =>
+            hexdump(&bytes[..bytes.len().min(HEXDUMP_LEN)]),
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(_) => text.to_string(),
+    }
+}
+
+/// Turn the request of a HAR entry into a request, if it has a url
+fn entry_request(har_req: &Value) -> Option<Request> {
+    let url = har_req["url"].as_str()?;
+    let method = har_req["method"].as_str()
+        .unwrap_or("GET")
+        .to_uppercase();
+
+    // Name the request after its method and path
+    let path = reqwest::Url::parse(url)
+        .map(|parsed| parsed.path().to_string())
+        .unwrap_or_else(|_| url.to_string());
+    let mut req = Request::new(format!("{} {}", method, path));
+    req.set_field_text(ReqField::Method, &method);
+    req.url = url.to_string();
+
+    // HTTP/2 pseudo headers can't be sent, the length is worked out again
+    // when the request is sent, and the content type is kept with the body
+    req.headers = har_req["headers"].as_array()
+        .map(|headers| headers.iter()
+            .filter_map(|header| Some((header["name"].as_str()?,
+                header["value"].as_str().unwrap_or_default())))
+            .filter(|(name, _)| !name.starts_with(':')
+                && !name.eq_ignore_ascii_case("content-length")
+                && !name.eq_ignore_ascii_case("content-type"))
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect::<Vec<_>>()
+            .join("\n"))
+        .unwrap_or_default();
+
+    if let Some(post_data) = har_req.get("postData") {
+        set_body(&mut req, post_data);
+    }
+
+    Some(req)
+}
+
+/// Set the body of a request from the `postData` of a HAR request
+fn set_body(req: &mut Request, post_data: &Value) {
+    let mime_type = post_data["mimeType"].as_str().unwrap_or_default();
+    let params = post_data["params"].as_array()
+        .filter(|params| !params.is_empty());
+
+    match params {
+        Some(params) if mime_type.starts_with("multipart/form-data")
+            || mime_type.starts_with("application/x-www-form-urlencoded") => {
+
+            req.body_mode = if mime_type.starts_with("multipart/form-data") {
+                BodyMode::Multipart
+            } else {
+                BodyMode::FormUrlEncoded
+            };
+            req.form = params.iter()
+                .filter_map(|param| Some(FormField {
+                    key: param["name"].as_str()?.to_string(),
+                    // Captured files aren't in the HAR, so point at a file
+                    // with the same name
+                    value: match param["fileName"].as_str() {
+                        Some(file_name) => format!("@{}", file_name),
+                        None => param["value"].as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                    },
+                }))
+                .collect();
+        },
+        _ => {
+            req.body_mode = BodyMode::Raw;
+            if !mime_type.is_empty() {
+                // Drop parameters like the charset
+                req.content_type = mime_type.split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+            }
+            req.body = post_data["text"].as_str()
+                .unwrap_or_default()
+                .to_string();
+        },
+    }
+}
+
+/// Build a HAR document from entries
+pub fn document(entries: Vec<Value>) -> Value {
+    json!({
+        "log": {
+            "version": HAR_VERSION,
+            "creator": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "entries": entries,
+        },
+    })
+}
+
+/// Build the HAR entry for a request and its last response
+///
+/// `resolved` is the request with its variables replaced, as it was sent
+pub fn entry(req: &Request, resolved: &Request) -> Value {
+    let receive_time = req.download.as_ref()
+        .map_or(Duration::ZERO, |download| download.duration);
+    let wait = req.wait_time.as_secs_f64() * 1000.0;
+    let receive = receive_time.as_secs_f64() * 1000.0;
+    let started = req.sent_at.unwrap_or_else(SystemTime::now);
+
+    json!({
+        "startedDateTime": iso_time(started),
+        "time": wait + receive,
+        "request": entry_request_json(resolved),
+        "response": entry_response_json(req),
+        "cache": {},
+        // The time to send the request isn't measured apart from waiting for
+        // the response
+        "timings": {
+            "send": 0,
+            "wait": wait,
+            "receive": receive,
+        },
+    })
+}
+
+/// Build the request object of a HAR entry
+fn entry_request_json(req: &Request) -> Value {
+    let query: Vec<Value> = reqwest::Url::parse(&req.url)
+        .map(|url| url.query_pairs()
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect())
+        .unwrap_or_default();
+
+    let mut har_req = json!({
+        "method": req.req_type,
+        "url": req.url,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": name_values(&req.header_pairs()),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": -1,
+    });
+
+    let post_data = match req.body_mode {
+        BodyMode::Raw if !req.body.is_empty() => Some(json!({
+            "mimeType": req.content_type,
+            "text": req.body,
+        })),
+        BodyMode::FormUrlEncoded | BodyMode::Multipart
+            if !req.form.is_empty() => {
+
+            let mime_type = if req.body_mode == BodyMode::Multipart {
+                "multipart/form-data"
+            } else {
+                "application/x-www-form-urlencoded"
+            };
+            let params: Vec<Value> = req.form.iter()
+                .map(|field| match field.value.strip_prefix('@') {
+                    Some(path) if req.body_mode == BodyMode::Multipart =>
+                        json!({ "name": field.key, "fileName": path }),
+                    _ => json!({ "name": field.key, "value": field.value }),
+                })
+                .collect();
+
+            Some(json!({ "mimeType": mime_type, "params": params }))
+        },
+        BodyMode::GraphQl => req.graphql.to_json().ok().map(|body| json!({
+            "mimeType": "application/json",
+            "text": body.to_string(),
+        })),
+        _ => None,
+    };
+    if let Some(post_data) = post_data {
+        har_req["postData"] = post_data;
+    }
+
+    har_req
+}
+
+/// Build the response object of a HAR entry
+fn entry_response_json(req: &Request) -> Value {
+    let status = req.status.split_whitespace()
+        .next()
+        .and_then(|code| code.parse::<u16>().ok())
+        .unwrap_or(0);
+    let status_text = StatusCode::from_u16(status).ok()
+        .and_then(|code| code.canonical_reason())
+        .unwrap_or_default();
+    let mime_type = req.resp_headers.iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.as_str())
+        .unwrap_or_default();
+
+    // Requests that weren't run here, like imported ones, only have the
+    // text of their response
+    let body = match &req.download {
+        Some(download) if download.is_done =>
+            fs::read(&download.body_file.path).ok(),
+        Some(_) => None,
+        None if status > 0 => Some(req.resp.clone().into_bytes()),
+        None => None,
+    };
+    let size = body.as_ref().map_or(-1, |body| body.len() as i64);
+    let mut content = json!({ "size": size, "mimeType": mime_type });
+    match body.map(String::from_utf8) {
+        Some(Ok(text)) => content["text"] = Value::String(text),
+        // Binary bodies are encoded
+        Some(Err(e)) => {
+            content["text"] = Value::String(base64::encode(e.as_bytes()));
+            content["encoding"] = Value::from("base64");
+        },
+        None => (),
+    }
+
+    json!({
+        "status": status,
+        "statusText": status_text,
+        "httpVersion": req.resp_version,
+        "cookies": [],
+        "headers": name_values(&req.resp_headers),
+        "content": content,
+        "redirectURL": "",
+        "headersSize": -1,
+        "bodySize": size,
+    })
+}
+
+/// Turn name/value pairs into HAR `{ name, value }` objects
+fn name_values(pairs: &[(String, String)]) -> Vec<Value> {
+    pairs.iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+/// Format a time as an ISO 8601 date and time in UTC, like
+/// `2024-01-31T12:00:00.000Z`
+fn iso_time(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_date((secs / 86_400) as i64);
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z", year, month, day,
+        secs / 3600 % 24, secs / 60 % 60, secs % 60,
+        since_epoch.subsec_millis())
+}
+
+/// Turn a number of days since 1970-01-01 into a year, month and day
+///
+/// Uses Howard Hinnant's `civil_from_days` algorithm
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era
+        - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        entry,
+        to_collection,
+        BodyMode,
+    };
+
+    #[test]
+    fn imports_requests_and_their_responses() {
+        let har = json!({ "log": { "entries": [{
+            "request": {
+                "method": "post",
+                "url": "https://example.com/pets?limit=2",
+                "headers": [
+                    { "name": ":authority", "value": "example.com" },
+                    { "name": "Accept", "value": "application/json" },
+                    { "name": "Content-Length", "value": "7" },
+                ],
+                "postData": {
+                    "mimeType": "application/x-www-form-urlencoded",
+                    "params": [{ "name": "name", "value": "Rex" }],
+                },
+            },
+            "response": {
+                "status": 201,
+                "content": { "text": "{\"id\":1}" },
+            },
+        }] } });
+
+        let requests = to_collection(&har, "Captured").unwrap().requests;
+        assert_eq!(requests.len(), 1);
+        let req = &requests[0];
+        assert_eq!(req.name, "POST /pets");
+        assert_eq!(req.folder, "Captured");
+        assert_eq!(req.req_type, "POST");
+        assert_eq!(req.headers, "Accept: application/json");
+        assert!(req.body_mode == BodyMode::FormUrlEncoded);
+        assert_eq!(req.form[0].key, "name");
+        assert_eq!(req.status, "201");
+        assert_eq!(req.resp, "{\"id\":1}");
+    }
+
+    #[test]
+    fn imports_multipart_bodies_without_their_content_type() {
+        let har = json!({ "log": { "entries": [{
+            "request": {
+                "method": "POST",
+                "url": "https://example.com/uploads",
+                "headers": [
+                    { "name": "Content-Type",
+                        "value": "multipart/form-data; boundary=old" },
+                    { "name": "X-Key", "value": "secret" },
+                ],
+                "postData": {
+                    "mimeType": "multipart/form-data; boundary=old",
+                    "params": [
+                        { "name": "title", "value": "Rex" },
+                        { "name": "photo", "fileName": "rex.png" },
+                    ],
+                },
+            },
+            "response": { "status": 0 },
+        }] } });
+
+        let req = &to_collection(&har, "").unwrap().requests[0];
+        assert_eq!(req.headers, "X-Key: secret");
+        assert!(req.body_mode == BodyMode::Multipart);
+        let form: Vec<(&str, &str)> = req.form.iter()
+            .map(|field| (field.key.as_str(), field.value.as_str()))
+            .collect();
+        assert_eq!(form, [("title", "Rex"), ("photo", "@rex.png")]);
+    }
+
+    #[test]
+    fn decodes_base64_bodies() {
+        let har = json!({ "log": { "entries": [
+            {
+                "request": { "url": "https://example.com/text" },
+                "response": { "status": 200, "content": {
+                    "text": "aGVsbG8=",
+                    "encoding": "base64",
+                } },
+            },
+            {
+                "request": { "url": "https://example.com/binary" },
+                "response": { "status": 200, "content": {
+                    "text": "AAEC",
+                    "encoding": "base64",
+                } },
+            },
+        ] } });
+
+        let requests = to_collection(&har, "").unwrap().requests;
+        assert_eq!(requests[0].resp, "hello");
+        assert!(requests[1].resp.starts_with("00000000  00 01 02"));
+    }
+
+    #[test]
+    fn exports_imported_responses() {
+        let har = json!({ "log": { "entries": [{
+            "request": { "method": "GET", "url": "https://example.com/pets" },
+            "response": { "status": 200, "content": { "text": "[]" } },
+        }] } });
+        let req = &to_collection(&har, "").unwrap().requests[0];
+
+        let exported = entry(req, req);
+        assert_eq!(exported["request"]["url"], "https://example.com/pets");
+        assert_eq!(exported["response"]["status"], 200);
+        assert_eq!(exported["response"]["content"]["text"], "[]");
+        assert_eq!(exported["response"]["content"]["size"], 2);
+    }
+}
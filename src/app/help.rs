@@ -0,0 +1,166 @@
+//! # help
+//!
+//! `help` lists the keys for what can be done in the current mode and pane.
+//! The list is made from the key bindings, so it follows the config file.
+
+use super::{
+    command::COMMANDS,
+    config::Action,
+    App,
+    InputStatus,
+    SelElement,
+};
+
+impl App {
+    /// Title of the help popup, naming the mode and pane it's for
+    pub fn help_title(&self) -> String {
+        let pane = match self.sel_element {
+            SelElement::LIST => "request list",
+            SelElement::INFOPANEL => "request info",
+        };
+
+        match self.input_status {
+            InputStatus::NORMAL => format!("Keys in the {}", pane),
+            InputStatus::INSERT => String::from("Keys while editing"),
+            InputStatus::COMMAND => String::from("Commands"),
+        }
+    }
+
+    /// Keys, and what they do in the current mode and pane
+    pub fn help_entries(&self) -> Vec<(String, String)> {
+        let list = self.sel_element == SelElement::LIST;
+        let mut entries: Vec<(String, String)> = Action::ALL.iter()
+            .filter_map(|action| {
+                let description = match self.input_status {
+                    InputStatus::NORMAL if !action.is_insert() =>
+                        describe(*action, list),
+                    InputStatus::INSERT => describe_insert(*action),
+                    InputStatus::COMMAND => describe_command(*action),
+                    _ => None,
+                }?;
+
+                // Only keys that don't type text work while typing
+                let keys: Vec<String> = self.config.keys.keys(*action).iter()
+                    .filter(|key| self.input_status == InputStatus::NORMAL
+                        || action.is_insert() || !key.is_text())
+                    .map(|key| key.to_string())
+                    .collect();
+                if keys.is_empty() {
+                    return None;
+                }
+
+                Some((keys.join(", "), description.to_string()))
+            })
+            .collect();
+
+        if self.input_status == InputStatus::COMMAND {
+            entries.push((String::from("up, down"),
+                String::from("Bring back earlier commands")));
+            entries.extend(COMMANDS.iter()
+                .map(|(name, usage)| (format!(":{}", name),
+                    usage.to_string())));
+        }
+
+        entries
+    }
+}
+
+/// What an action does in the request list or the info panel
+///
+/// Returns `None` for actions that do nothing there
+fn describe(action: Action, list: bool) -> Option<&'static str> {
+    let description = match action {
+        Action::Quit => "Quit",
+        Action::Down if list => "Select the next request",
+        Action::Down => "Select the next field",
+        Action::Up if list => "Select the previous request",
+        Action::Up => "Select the previous field",
+        Action::MoveDown if list => "Move the request down the list",
+        Action::MoveUp if list => "Move the request up the list",
+        Action::ScrollDown if !list => "Scroll the response down",
+        Action::ScrollUp if !list => "Scroll the response up",
+        Action::ScrollLeft if !list => "Scroll the response left",
+        Action::ScrollRight if !list => "Scroll the response right",
+        Action::Insert => "Edit the selected field",
+        Action::SwitchPanel if list => "Go to the request info",
+        Action::SwitchPanel => "Go to the request list",
+        Action::Run => "Run the request",
+        Action::NextEnvironment => "Switch to the next environment",
+        Action::Close => "Close a WebSocket or event stream",
+        Action::Mark => "Mark the request to export",
+        Action::Export => "Export marked requests as HAR",
+        Action::SaveBody => "Save the response body to a file",
+        Action::LoadSchema => "Load the GraphQL schema",
+        Action::CycleChoice if !list => "Cycle the body mode or content type",
+        Action::New => "Create a request",
+        Action::Duplicate => "Copy the request",
+        Action::SaveTemplate => "Save the request as a template",
+        Action::Delete => "Delete the request",
+        Action::Undo => "Undo",
+        Action::Redo => "Redo",
+        Action::Command => "Type a command",
+        Action::OpenTab => "Open the request in a new tab",
+        Action::NextTab => "Go to the next tab",
+        Action::PreviousTab => "Go to the previous tab",
+        Action::Edit if list => "Edit the request as JSON in $EDITOR",
+        Action::Edit => "Edit the field in $EDITOR",
+        Action::Help => "Show or hide this help",
+        _ => return None,
+    };
+
+    Some(description)
+}
+
+/// What an action does while editing a field
+fn describe_insert(action: Action) -> Option<&'static str> {
+    let description = match action {
+        Action::Cancel => "Stop editing without saving",
+        Action::Submit => "Save the field, or send the WebSocket message",
+        Action::Newline => "Add a newline to a multiline field",
+        Action::Complete => "Complete GraphQL names in the query",
+        Action::Help => "Show or hide this help",
+        _ => return None,
+    };
+
+    Some(description)
+}
+
+/// What an action does while typing a command
+fn describe_command(action: Action) -> Option<&'static str> {
+    let description = match action {
+        Action::Cancel => "Close the command line",
+        Action::Submit => "Run the command",
+        Action::Complete => "Complete the command or argument",
+        Action::Help => "Show or hide this help",
+        _ => return None,
+    };
+
+    Some(description)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::{
+        test_util::app,
+        SelElement,
+    };
+
+    #[test]
+    fn lists_request_actions_in_both_panes() {
+        let mut app = app(&["Pets"]);
+
+        for pane in [SelElement::LIST, SelElement::INFOPANEL] {
+            app.sel_element = pane;
+            let described: Vec<String> = app.help_entries().into_iter()
+                .map(|(_, description)| description)
+                .collect();
+
+            for description in ["Mark the request to export", "Copy the request",
+                "Delete the request", "Open the request in a new tab"] {
+
+                assert!(described.iter().any(|d| d == description),
+                    "{} is missing", description);
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! # history
+//!
+//! `history` implements undoing and redoing changes to the request list, like
+//! creating, deleting, moving and editing requests. Each step saves or deletes
+//! the requests it touches, so the store matches the list.
+
+use serde_json::Value;
+
+use super::{
+    request::{
+        ReqField,
+        Request,
+    },
+    App,
+};
+
+/// The saved fields of a request, to bring back a deleted request
+pub struct Snapshot {
+    saved: Value,
+    // The `.http` file block the request came from, which isn't saved
+    http_block: Option<(usize, usize)>,
+}
+
+impl Snapshot {
+    /// Take a snapshot of a request
+    pub fn of(req: &Request) -> Self {
+        Self {
+            saved: serde_json::to_value(req).unwrap_or_default(),
+            http_block: req.http_block,
+        }
+    }
+
+    /// Build the request again
+    fn restore(&self) -> Option<Request> {
+        let mut req: Request = serde_json::from_value(self.saved.clone())
+            .ok()?;
+        req.http_block = self.http_block;
+
+        Some(req)
+    }
+}
+
+/// A change to the request list that can be undone
+pub enum Change {
+    // A request was added at an index
+    Create(usize, Snapshot),
+    // The request at an index was deleted
+    Delete(usize, Snapshot),
+    // The request at an index swapped places with the one at another index
+    Swap(usize, usize),
+    // All the saved fields of the request at an index changed at once
+    Replace {
+        index: usize,
+        before: Snapshot,
+        after: Snapshot,
+    },
+    // A field of the request at an index changed from one text to another
+    Edit {
+        index: usize,
+        field: ReqField,
+        before: String,
+        after: String,
+    },
+}
+
+impl Change {
+    /// Describe the change for the status line
+    fn describe(&self) -> &'static str {
+        match self {
+            Change::Create(..) => "creating a request",
+            Change::Delete(..) => "deleting a request",
+            Change::Swap(..) => "moving a request",
+            Change::Replace { .. } | Change::Edit { .. } =>
+                "editing a request",
+        }
+    }
+}
+
+/// Changes that can be undone, and undone changes that can be redone
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+
+impl History {
+    /// Remember a change so it can be undone
+    ///
+    /// Undone changes can't be redone after a new change
+    pub fn record(&mut self, change: Change) {
+        self.undo.push(change);
+        self.redo.clear();
+    }
+}
+
+impl App {
+    /// Undo the last change to the request list
+    pub fn undo(&mut self) {
+        match self.history.undo.pop() {
+            Some(change) => {
+                self.status_line = format!("Undid {}", change.describe());
+                self.apply(&change, false);
+                self.history.redo.push(change);
+            },
+            None => self.status_line = String::from("Nothing to undo"),
+        }
+    }
+
+    /// Redo the last undone change to the request list
+    pub fn redo(&mut self) {
+        match self.history.redo.pop() {
+            Some(change) => {
+                self.status_line = format!("Redid {}", change.describe());
+                self.apply(&change, true);
+                self.history.undo.push(change);
+            },
+            None => self.status_line = String::from("Nothing to redo"),
+        }
+    }
+
+    /// Make a change again, or reverse it, and select the request it changed
+    fn apply(&mut self, change: &Change, forward: bool) {
+        match change {
+            Change::Create(index, snapshot) | Change::Delete(index, snapshot)
+                if forward == matches!(change, Change::Create(..)) => {
+
+                if let Some(req) = snapshot.restore() {
+                    self.insert_request(*index, req);
+                }
+            },
+            Change::Create(index, _) | Change::Delete(index, _) => {
+                self.remove_request(*index);
+            },
+            Change::Swap(from, to) => {
+                self.requests.swap(*from, *to);
+                self.tabs_swapped(*from, *to);
+                self.req_index = if forward { *to } else { *from };
+            },
+            Change::Replace { index, before, after } => {
+                let snapshot = if forward { after } else { before };
+                if let Some(req) = snapshot.restore() {
+                    self.requests[*index].set_saved_fields(req);
+                }
+                self.req_index = *index;
+                self.save_request(*index);
+            },
+            Change::Edit { index, field, before, after } => {
+                let text = if forward { after } else { before };
+                self.requests[*index].set_field_text(*field, text);
+                self.req_index = *index;
+                self.save_request(*index);
+            },
+        }
+
+        self.update_inputs();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Change,
+        Snapshot,
+    };
+    use crate::app::test_util::{
+        app,
+        listed,
+        saved,
+    };
+
+    #[test]
+    fn undoes_and_redoes_edits() {
+        let mut app = app(&["Pets", "Store"]);
+        app.req_index = 1;
+        app.run_command("rename Shop").unwrap();
+        app.req_index = 0;
+
+        app.undo();
+        assert_eq!(listed(&app), ["Pets", "Store"]);
+        assert_eq!(saved(&app), ["Pets", "Store"]);
+        assert_eq!(app.req_index, 1);
+
+        app.redo();
+        assert_eq!(listed(&app), ["Pets", "Shop"]);
+        assert_eq!(saved(&app), ["Pets", "Shop"]);
+    }
+
+    #[test]
+    fn undoes_and_redoes_creating_and_deleting() {
+        let mut app = app(&["Pets"]);
+        app.create_request(None);
+        app.req_index = 0;
+        app.delete_request();
+        assert_eq!(listed(&app), ["Request #2"]);
+
+        app.undo();
+        assert_eq!(listed(&app), ["Pets", "Request #2"]);
+        app.undo();
+        assert_eq!(listed(&app), ["Pets"]);
+        assert_eq!(saved(&app), ["Pets"]);
+
+        app.redo();
+        app.redo();
+        assert_eq!(listed(&app), ["Request #2"]);
+        assert_eq!(saved(&app), ["Request #2"]);
+    }
+
+    #[test]
+    fn undoes_and_redoes_replacing_a_request() {
+        let mut app = app(&["Pets"]);
+        let before = Snapshot::of(&app.requests[0]);
+        app.requests[0].url = String::from("https://example.com/pets");
+        app.requests[0].body = String::from("{}");
+        app.history.record(Change::Replace {
+            index: 0,
+            before,
+            after: Snapshot::of(&app.requests[0]),
+        });
+
+        app.undo();
+        assert_eq!(app.requests[0].url, "");
+        assert_eq!(app.requests[0].body, "");
+
+        app.redo();
+        assert_eq!(app.requests[0].url, "https://example.com/pets");
+        assert_eq!(app.requests[0].body, "{}");
+    }
+
+    #[test]
+    fn new_changes_clear_the_redo_history() {
+        let mut app = app(&["Pets"]);
+        app.run_command("rename Dogs").unwrap();
+        app.undo();
+        app.run_command("rename Cats").unwrap();
+
+        app.redo();
+        assert_eq!(app.status_line, "Nothing to redo");
+        assert_eq!(listed(&app), ["Cats"]);
+
+        app.undo();
+        app.undo();
+        assert_eq!(app.status_line, "Nothing to undo");
+        assert_eq!(listed(&app), ["Pets"]);
+    }
+}
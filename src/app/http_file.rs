@@ -0,0 +1,726 @@
+//! # http_file
+//!
+//! The `http_file` crate reads `.http` files, as used by the VS Code REST
+//! Client and JetBrains HTTP Client, into requests, and writes edited requests
+//! back to them. Comments, separators and variable definitions are kept as
+//! they were, and requests that weren't changed are written back unchanged.
+
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use super::{
+    environment::{
+        self,
+        Variables,
+    },
+    request::{
+        BodyMode,
+        FormField,
+        ReqField,
+        Request,
+    },
+    App,
+};
+
+/// Header REST Client uses to mark GraphQL requests
+const REQUEST_TYPE_HEADER: &str = "X-Request-Type";
+
+/// Boundary used when writing multipart bodies
+const BOUNDARY: &str = "AlmagroBoundary";
+
+/// The requests read from a `.http` file, with the id of the block each
+/// request came from
+pub type FileRequests = Vec<(usize, Request)>;
+
+/// A `.http` file opened for editing
+pub struct HttpFile {
+    pub path: PathBuf,
+    // Variables defined in the file with `@name = value`
+    pub variables: Variables,
+    blocks: Vec<Block>,
+    // Lines after the last request, like comments
+    tail: Vec<String>,
+    // Whether the file ended with a newline
+    final_newline: bool,
+    // The file's line ending, kept when it's written back
+    line_ending: &'static str,
+}
+
+/// A request in a `.http` file, and the lines around it
+struct Block {
+    // Identifies the block, even after other blocks are removed
+    id: usize,
+    // Separator, comment and variable lines before the request line
+    leading: Vec<String>,
+    // The request as it was written, and as it would be written when parsed
+    lines: Vec<String>,
+    parsed: String,
+    name: String,
+    // HTTP version written after the url, if any
+    version: Option<String>,
+    // Blank lines after the request
+    trailing: Vec<String>,
+    // Whether the request was deleted, so the block isn't written
+    removed: bool,
+}
+
+impl HttpFile {
+    /// Read a `.http` file
+    ///
+    /// Returns the file and its requests, with the id of the block each
+    /// request came from
+    pub fn open(path: &Path)
+        -> Result<(Self, FileRequests), Box<dyn std::error::Error>> {
+
+        let text = fs::read_to_string(path)?;
+        let folder = path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut file = Self {
+            path: path.to_path_buf(),
+            variables: Variables::new(),
+            blocks: Vec::new(),
+            tail: Vec::new(),
+            final_newline: text.ends_with('\n'),
+            line_ending: if text.contains("\r\n") { "\r\n" } else { "\n" },
+        };
+        let mut requests = Vec::new();
+
+        // Lines that aren't part of a request yet
+        let mut pending: Vec<String> = Vec::new();
+
+        for segment in segments(&text) {
+            let request_line = segment.iter()
+                .position(|line| !is_meta_line(line));
+
+            let start = match request_line {
+                Some(start) => start,
+                None => {
+                    pending.extend(segment);
+                    continue;
+                },
+            };
+
+            let mut leading = std::mem::take(&mut pending);
+            leading.extend(segment[..start].iter().cloned());
+            file.add_variables(&leading);
+
+            let end = segment.iter()
+                .rposition(|line| !line.trim().is_empty())
+                .map_or(segment.len(), |last| last + 1);
+            let lines = segment[start..end].to_vec();
+            let trailing = segment[end..].to_vec();
+
+            let (mut req, version) = parse_request(&lines);
+            req.name = block_name(&leading)
+                .unwrap_or_else(|| default_name(&req));
+            req.folder = folder.clone();
+
+            let id = file.blocks.len();
+            file.blocks.push(Block {
+                id,
+                leading,
+                parsed: request_text(&req, version.as_deref()).join("\n"),
+                lines,
+                name: req.name.clone(),
+                version,
+                trailing,
+                removed: false,
+            });
+            requests.push((id, req));
+        }
+
+        file.add_variables(&pending);
+        file.tail = pending;
+
+        Ok((file, requests))
+    }
+
+    /// Add the `@name = value` variables defined in lines
+    ///
+    /// Values can use variables defined before them
+    fn add_variables(&mut self, lines: &[String]) {
+        for line in lines {
+            let definition = line.trim()
+                .strip_prefix('@')
+                .and_then(|definition| definition.split_once('='));
+
+            if let Some((name, value)) = definition {
+                let value = environment::substitute(value.trim(),
+                    &self.variables);
+                self.variables.insert(name.trim().to_string(), value);
+            }
+        }
+    }
+
+    /// Remove the block a request came from
+    ///
+    /// The block is kept so it can be restored
+    pub fn remove(&mut self, id: usize) {
+        self.set_removed(id, true);
+    }
+
+    /// Restore a removed block
+    pub fn restore(&mut self, id: usize) {
+        self.set_removed(id, false);
+    }
+
+    fn set_removed(&mut self, id: usize, removed: bool) {
+        if let Some(block) = self.blocks.iter_mut()
+            .find(|block| block.id == id) {
+
+            block.removed = removed;
+        }
+    }
+
+    /// Write the file's text, with the current state of its requests
+    ///
+    /// Takes in the requests from the file and the ids of their blocks.
+    /// Blocks without a request are written as they were read
+    pub fn to_text(&self, requests: &[(usize, &Request)]) -> String {
+        let mut lines: Vec<String> = Vec::new();
+
+        for block in &self.blocks {
+            // Variables defined before a removed request are still used by
+            // the others
+            if block.removed {
+                lines.extend(block.leading.iter()
+                    .filter(|line| line.trim_start().starts_with('@'))
+                    .cloned());
+                continue;
+            }
+
+            let req = requests.iter()
+                .find(|(id, _)| *id == block.id)
+                .map(|(_, req)| *req);
+
+            match req {
+                Some(req) => {
+                    lines.extend(block.leading_for(&req.name));
+
+                    let text = request_text(req, block.version.as_deref());
+                    if text.join("\n") == block.parsed {
+                        lines.extend(block.lines.iter().cloned());
+                    } else {
+                        lines.extend(text);
+                    }
+                },
+                None => {
+                    lines.extend(block.leading.iter().cloned());
+                    lines.extend(block.lines.iter().cloned());
+                },
+            }
+
+            lines.extend(block.trailing.iter().cloned());
+        }
+
+        lines.extend(self.tail.iter().cloned());
+
+        let mut text = lines.join(self.line_ending);
+        if self.final_newline {
+            text.push_str(self.line_ending);
+        }
+        text
+    }
+}
+
+impl Block {
+    /// The lines before the request, naming it `name`
+    ///
+    /// Renames the request's `@name` comment or separator if it has one, or
+    /// adds an `@name` comment
+    fn leading_for(&self, name: &str) -> Vec<String> {
+        let mut leading = self.leading.clone();
+        if name == self.name {
+            return leading;
+        }
+
+        if let Some(line) = leading.iter_mut()
+            .find(|line| name_comment(line).is_some()) {
+
+            let prefix = if line.trim_start().starts_with("//") {
+                "//"
+            } else {
+                "#"
+            };
+            *line = format!("{} @name {}", prefix, name);
+        } else if let Some(line) = leading.iter_mut()
+            .rev()
+            .find(|line| line.starts_with("###")
+                && !line[3..].trim().is_empty()) {
+
+            *line = format!("### {}", name);
+        } else {
+            leading.push(format!("# @name {}", name));
+        }
+
+        leading
+    }
+}
+
+/// Split a file into lines, starting a new segment at each `###` separator
+fn segments(text: &str) -> Vec<Vec<String>> {
+    let mut segments = vec![Vec::new()];
+
+    for line in text.lines() {
+        if line.starts_with("###") {
+            segments.push(Vec::new());
+        }
+        segments.last_mut()
+            .unwrap()
+            .push(line.to_string());
+    }
+
+    segments
+}
+
+/// Is the line blank, a comment, a separator or a variable definition,
+/// rather than part of a request?
+fn is_meta_line(line: &str) -> bool {
+    let line = line.trim();
+
+    line.is_empty() || line.starts_with('#') || line.starts_with("//")
+        || line.starts_with('@')
+}
+
+/// The name in a `# @name` or `// @name` comment
+fn name_comment(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let comment = line.strip_prefix('#')
+        .or_else(|| line.strip_prefix("//"))?;
+
+    comment.trim()
+        .strip_prefix("@name")
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+}
+
+/// Name a request after its `@name` comment, or the text of its separator
+fn block_name(leading: &[String]) -> Option<String> {
+    leading.iter()
+        .find_map(|line| name_comment(line))
+        .or_else(|| leading.iter()
+            .rev()
+            .find(|line| line.starts_with("###"))
+            .map(|line| line[3..].trim())
+            .filter(|name| !name.is_empty()))
+        .map(|name| name.to_string())
+}
+
+/// Name a request after its method and path
+fn default_name(req: &Request) -> String {
+    let path = reqwest::Url::parse(&req.url)
+        .map(|url| url.path().to_string())
+        .unwrap_or_else(|_| req.url.clone());
+
+    format!("{} {}", req.req_type, path)
+}
+
+/// Parse the lines of a request, from the request line to the end of the body
+///
+/// Returns the request and the HTTP version written after its url, if any
+fn parse_request(lines: &[String]) -> (Request, Option<String>) {
+    let mut req = Request::new("");
+
+    // The request line is `[METHOD] url [HTTP/version]`
+    let parts: Vec<&str> = lines[0].split_whitespace().collect();
+    let (method, rest) = match parts.as_slice() {
+        [method, rest @ ..] if !rest.is_empty()
+            && method.chars().all(|c| c.is_ascii_uppercase()) =>
+            (*method, rest),
+        _ => ("GET", parts.as_slice()),
+    };
+    let (url_parts, version) = match rest {
+        [url @ .., version] if version.starts_with("HTTP/") =>
+            (url, Some(version.to_string())),
+        _ => (rest, None),
+    };
+    req.set_field_text(ReqField::Method, method);
+    req.url = url_parts.join(" ");
+
+    // Query parameters can continue the url on the next lines
+    let mut index = 1;
+    while let Some(line) = lines.get(index)
+        .map(|line| line.trim())
+        .filter(|line| line.starts_with('?') || line.starts_with('&')) {
+
+        req.url.push_str(line);
+        index += 1;
+    }
+
+    let mut headers = Vec::new();
+    while let Some(line) = lines.get(index)
+        .filter(|line| !line.trim().is_empty()) {
+
+        let line = line.trim();
+        if !line.starts_with('#') && !line.starts_with("//") {
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_string(),
+                    value.trim().to_string()));
+            }
+        }
+        index += 1;
+    }
+
+    let body = lines.get(index + 1..)
+        .map(|body| body.join("\n"))
+        .unwrap_or_default();
+
+    set_body(&mut req, headers, body);
+
+    (req, version)
+}
+
+/// Set the headers and body of a request, working out the body mode from the
+/// headers
+fn set_body(req: &mut Request, mut headers: Vec<(String, String)>,
+    body: String) {
+
+    let take_header = |headers: &mut Vec<(String, String)>, name: &str| {
+        headers.iter()
+            .position(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|index| headers.remove(index).1)
+    };
+
+    let is_graphql = take_header(&mut headers, REQUEST_TYPE_HEADER)
+        .is_some_and(|value| value.eq_ignore_ascii_case("graphql"));
+
+    // Files sent as the body keep their content type as a header
+    let file = body.trim()
+        .strip_prefix('<')
+        .filter(|path| !path.contains('\n'))
+        .map(|path| path.trim().to_string());
+
+    let content_type = match file {
+        Some(_) => None,
+        None => take_header(&mut headers, "Content-Type"),
+    };
+    let mime_type = content_type.as_deref()
+        .and_then(|content_type| content_type.split(';').next())
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    if is_graphql {
+        let (query, variables) = body.split_once("\n\n")
+            .unwrap_or((&body, ""));
+        req.body_mode = BodyMode::GraphQl;
+        req.graphql.query = query.to_string();
+        req.graphql.variables = variables.trim().to_string();
+    } else if let Some(path) = file {
+        req.body_mode = BodyMode::Binary;
+        req.body_file = path;
+    } else if mime_type == "application/x-www-form-urlencoded" {
+        req.body_mode = BodyMode::FormUrlEncoded;
+        req.form = FormField::parse_list(body.trim());
+    } else if let Some(form) = content_type.as_deref()
+        .filter(|_| mime_type == "multipart/form-data")
+        .and_then(|content_type| parse_multipart(content_type, &body)) {
+
+        req.body_mode = BodyMode::Multipart;
+        req.form = form;
+    } else {
+        req.body_mode = BodyMode::Raw;
+        if let Some(content_type) = content_type {
+            req.content_type = content_type;
+        }
+        req.body = body;
+    }
+
+    req.headers = headers.iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect::<Vec<_>>()
+        .join("\n");
+}
+
+/// Parse the parts of a multipart body into form fields
+///
+/// Parts with a `< path` body are files. Returns `None` if the body can't be
+/// parsed
+fn parse_multipart(content_type: &str, body: &str) -> Option<Vec<FormField>> {
+    let boundary = content_type.split(';')
+        .find_map(|param| param.trim().strip_prefix("boundary="))?
+        .trim_matches('"');
+    let delimiter = format!("--{}", boundary);
+
+    let mut form = Vec::new();
+    for part in body.split(&delimiter).skip(1) {
+        if part.starts_with("--") {
+            break;
+        }
+
+        let part = part.trim_start_matches(['\r', '\n']);
+        let (part_headers, content) = part.split_once("\n\n")?;
+        let key = part_headers.lines()
+            .find(|line| line.to_ascii_lowercase()
+                .starts_with("content-disposition"))?
+            .split(';')
+            .find_map(|param| param.trim().strip_prefix("name="))?
+            .trim_matches('"')
+            .to_string();
+
+        let content = content.trim_end_matches(['\r', '\n']);
+        let value = match content.strip_prefix('<') {
+            Some(path) => format!("@{}", path.trim()),
+            None => content.to_string(),
+        };
+        form.push(FormField { key, value });
+    }
+
+    Some(form)
+}
+
+/// Write a request as the lines of a `.http` request
+fn request_text(req: &Request, version: Option<&str>) -> Vec<String> {
+    let mut lines = vec![match version {
+        Some(version) => format!("{} {} {}", req.req_type, req.url, version),
+        None => format!("{} {}", req.req_type, req.url),
+    }];
+    lines.extend(req.headers.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.to_string()));
+
+    let body = match req.body_mode {
+        BodyMode::Raw if !req.body.is_empty() => {
+            lines.push(format!("Content-Type: {}", req.content_type));
+            req.body.clone()
+        },
+        BodyMode::FormUrlEncoded if !req.form.is_empty() => {
+            lines.push(String::from(
+                "Content-Type: application/x-www-form-urlencoded"));
+            FormField::format_list(&req.form)
+        },
+        BodyMode::Multipart if !req.form.is_empty() => {
+            lines.push(format!("Content-Type: multipart/form-data; \
+                boundary={}", BOUNDARY));
+            multipart_text(&req.form)
+        },
+        BodyMode::Binary if !req.body_file.is_empty() =>
+            format!("< {}", req.body_file),
+        BodyMode::GraphQl => {
+            lines.push(format!("{}: GraphQL", REQUEST_TYPE_HEADER));
+            if req.graphql.variables.trim().is_empty() {
+                req.graphql.query.clone()
+            } else {
+                format!("{}\n\n{}", req.graphql.query, req.graphql.variables)
+            }
+        },
+        _ => String::new(),
+    };
+
+    if !body.is_empty() {
+        lines.push(String::new());
+        lines.extend(body.lines().map(|line| line.to_string()));
+    }
+
+    lines
+}
+
+/// Write form fields as a multipart body, with `< path` bodies for files
+fn multipart_text(form: &[FormField]) -> String {
+    let mut text = String::new();
+
+    for field in form {
+        text.push_str(&format!("--{}\n", BOUNDARY));
+        match field.value.strip_prefix('@') {
+            Some(path) => {
+                let file_name = Path::new(path).file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                text.push_str(&format!("Content-Disposition: form-data; \
+                    name=\"{}\"; filename=\"{}\"\n\n< {}\n", field.key,
+                    file_name, path));
+            },
+            None => text.push_str(&format!("Content-Disposition: form-data; \
+                name=\"{}\"\n\n{}\n", field.key, field.value)),
+        }
+    }
+
+    text.push_str(&format!("--{}--", BOUNDARY));
+    text
+}
+
+impl App {
+    /// Open a `.http` file, adding its requests to the request list
+    ///
+    /// Changes to the requests are written back to the file instead of the
+    /// database. Returns the number of requests opened
+    pub fn open_http_file(&mut self, path: &Path)
+        -> Result<usize, Box<dyn std::error::Error>> {
+
+        let (file, requests) = HttpFile::open(path)?;
+        let file_index = self.http_files.len();
+        self.http_files.push(file);
+
+        let count = requests.len();
+        for (id, mut req) in requests {
+            req.http_block = Some((file_index, id));
+            self.requests.push(req);
+        }
+
+        // Select the first request in the file
+        if count > 0 {
+            self.req_index = self.requests.len() - count;
+            self.update_inputs();
+        }
+
+        Ok(count)
+    }
+
+    /// Write the requests from an opened `.http` file back to it
+    pub fn write_http_file(&self, file_index: usize)
+        -> Result<(), Box<dyn std::error::Error>> {
+
+        let requests: Vec<(usize, &Request)> = self.requests.iter()
+            .filter_map(|req| match req.http_block {
+                Some((index, id)) if index == file_index => Some((id, req)),
+                _ => None,
+            })
+            .collect();
+
+        let file = &self.http_files[file_index];
+        fs::write(&file.path, file.to_text(&requests))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        fs,
+        process,
+    };
+
+    use super::{
+        FileRequests,
+        HttpFile,
+    };
+
+    const FILE: &str = "\
+@host = https://example.com
+@api = {{host}}/api
+
+### List pets
+# Only the first page
+GET {{api}}/pets
+    ?limit=10
+Accept: application/json
+
+###
+
+# @name create
+POST {{api}}/pets HTTP/1.1
+Content-Type: application/json
+
+{
+  \"name\": \"Rex\"
+}
+
+###
+// Trailing comment
+";
+
+    /// Write text to a temporary `.http` file and open it
+    fn open(name: &str, text: &str) -> (HttpFile, FileRequests) {
+        let path = env::temp_dir()
+            .join(format!("almagro-test-{}-{}.http", process::id(), name));
+        fs::write(&path, text).unwrap();
+        let opened = HttpFile::open(&path).unwrap();
+        fs::remove_file(&path).ok();
+        opened
+    }
+
+    /// Write a file back with the requests as they are
+    fn write(file: &HttpFile, requests: &FileRequests) -> String {
+        let requests: Vec<_> = requests.iter()
+            .map(|(id, req)| (*id, req))
+            .collect();
+        file.to_text(&requests)
+    }
+
+    #[test]
+    fn reads_requests_and_variables() {
+        let (file, requests) = open("read", FILE);
+
+        assert_eq!(file.variables["api"], "https://example.com/api");
+        assert_eq!(requests.len(), 2);
+
+        let (_, list) = &requests[0];
+        assert_eq!(list.name, "List pets");
+        assert_eq!(list.req_type, "GET");
+        assert_eq!(list.url, "{{api}}/pets?limit=10");
+        assert_eq!(list.headers, "Accept: application/json");
+
+        let (_, create) = &requests[1];
+        assert_eq!(create.name, "create");
+        assert_eq!(create.content_type, "application/json");
+        assert_eq!(create.body, "{\n  \"name\": \"Rex\"\n}");
+    }
+
+    #[test]
+    fn writes_unchanged_files_as_they_were() {
+        let (file, requests) = open("unchanged", FILE);
+
+        assert_eq!(write(&file, &requests), FILE);
+    }
+
+    #[test]
+    fn writes_only_the_edited_request() {
+        let (file, mut requests) = open("edited", FILE);
+        requests[1].1.url = String::from("{{api}}/dogs");
+        requests[1].1.name = String::from("create dog");
+
+        let text = write(&file, &requests);
+        let expected = FILE
+            .replace("# @name create", "# @name create dog")
+            .replace("POST {{api}}/pets HTTP/1.1",
+                "POST {{api}}/dogs HTTP/1.1");
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn leaves_out_removed_requests() {
+        let (mut file, requests) = open("removed", FILE);
+        file.remove(requests[0].0);
+
+        let text = write(&file, &requests);
+        assert!(!text.contains("GET {{api}}/pets"));
+        assert!(text.starts_with("@host = https://example.com\n"));
+        assert!(text.contains("POST {{api}}/pets"));
+
+        file.restore(requests[0].0);
+        assert_eq!(write(&file, &requests), FILE);
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        let crlf = FILE.replace('\n', "\r\n");
+        let (file, mut requests) = open("crlf", &crlf);
+        assert_eq!(requests[0].1.url, "{{api}}/pets?limit=10");
+
+        requests[0].1.headers = String::from("Accept: text/plain");
+        let text = write(&file, &requests);
+        assert!(!text.replace("\r\n", "").contains('\n'));
+        assert!(text.contains("Accept: text/plain\r\n"));
+        assert!(text.ends_with("// Trailing comment\r\n"));
+    }
+
+    #[test]
+    fn names_requests_without_a_name() {
+        let (file, mut requests) = open("unnamed",
+            "GET https://example.com/a\n");
+        assert_eq!(requests[0].1.name, "GET /a");
+
+        requests[0].1.name = String::from("First");
+        assert_eq!(write(&file, &requests),
+            "# @name First\nGET https://example.com/a\n");
+    }
+}
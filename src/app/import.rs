@@ -0,0 +1,95 @@
+//! # import
+//!
+//! `import` implements methods to add the requests and environments in files
+//! from other tools to the app.
+
+use std::{
+    fs,
+    path::Path,
+};
+
+use super::{
+    environment::Environment,
+    har,
+    openapi,
+    request::Request,
+    App,
+};
+
+/// Requests and environments read from a file
+pub struct Collection {
+    pub requests: Vec<Request>,
+    pub environments: Vec<Environment>,
+}
+
+impl App {
+    /// Import the requests and environments in a file, working out its format
+    /// from its contents
+    ///
+    /// Returns the number of requests imported
+    pub fn import_file(&mut self, path: &Path)
+        -> Result<usize, Box<dyn std::error::Error>> {
+
+        let doc = openapi::load_document(path)?;
+
+        let collection = if openapi::is_spec(&doc) {
+            // Link the requests to the spec to validate their responses
+            self.load_spec(path)?;
+            let spec_path = fs::canonicalize(path)?.display().to_string();
+
+            let mut collection = openapi::to_collection(&doc)?;
+            for req in collection.requests.iter_mut() {
+                req.spec_path = spec_path.clone();
+            }
+            collection
+        } else if har::is_har(&doc) {
+            // Put the requests in a folder named after the file
+            let folder = path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            har::to_collection(&doc, &folder)?
+        } else {
+            return Err(format!("{} isn't a supported file format",
+                path.display()).into());
+        };
+
+        Ok(self.add_collection(collection))
+    }
+
+    /// Add and save the requests and environments in a collection
+    ///
+    /// Environments replace the variables of existing environments with the
+    /// same name. Returns the number of requests added
+    fn add_collection(&mut self, collection: Collection) -> usize {
+        let count = collection.requests.len();
+
+        for req in collection.requests {
+            self.requests.push(req);
+            self.save_request(self.requests.len() - 1);
+        }
+
+        for env in collection.environments {
+            let index = match self.environments.iter()
+                .position(|existing| existing.name == env.name) {
+
+                Some(index) => {
+                    self.environments[index].variables.extend(env.variables);
+                    index
+                },
+                None => {
+                    self.environments.push(env);
+                    self.environments.len() - 1
+                },
+            };
+            self.save_environment(index);
+        }
+
+        // Select the first imported request
+        if count > 0 {
+            self.req_index = self.requests.len() - count;
+            self.update_inputs();
+        }
+
+        count
+    }
+}
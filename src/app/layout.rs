@@ -0,0 +1,216 @@
+//! # layout
+//!
+//! `layout` works out how the UI fits the terminal: how tall each field of
+//! the info panel is, which fields are shown when they don't all fit, and
+//! where the cursor goes in text that's wrapped or has wide characters.
+
+use unicode_width::UnicodeWidthChar;
+
+/// Terminals narrower than this show the request list or the info panel,
+/// not both
+pub const NARROW_WIDTH: u16 = 60;
+
+/// Height of a field with one line, including its borders
+const LINE_HEIGHT: u16 = 3;
+
+/// Smallest height of a field with multiple lines, including its borders
+const MIN_MULTILINE_HEIGHT: u16 = 4;
+
+/// Smallest height of the response, including its borders
+pub const MIN_RESPONSE_HEIGHT: u16 = 5;
+
+/// Heights of the fields of the info panel, from whether each one has
+/// multiple lines, leaving `reserved` lines for the status and response
+///
+/// Fields with multiple lines get `multiline_percent` of the height, and
+/// shrink when there isn't room. If the fields still don't fit, only the
+/// ones around the selected field are shown, and the rest get no height
+pub fn field_heights(multiline: &[bool], selected: usize, height: u16,
+    reserved: u16, multiline_percent: u16) -> Vec<u16> {
+
+    let available = height.saturating_sub(reserved);
+    let lines = multiline.iter().filter(|multiline| !**multiline).count()
+        as u16;
+    let multilines = multiline.len() as u16 - lines;
+
+    let preferred = (height as u32 * multiline_percent as u32 / 100) as u16;
+    let multiline_height = available.saturating_sub(lines * LINE_HEIGHT)
+        .checked_div(multilines)
+        .map_or(0, |fitted| preferred.min(fitted).max(MIN_MULTILINE_HEIGHT));
+
+    let mut heights: Vec<u16> = multiline.iter()
+        .map(|multiline| if *multiline {
+            multiline_height
+        } else {
+            LINE_HEIGHT
+        })
+        .collect();
+    if heights.iter().sum::<u16>() <= available || heights.is_empty() {
+        return heights;
+    }
+
+    // Show the selected field, then as many after it and then before it as
+    // fit
+    let selected = selected.min(heights.len() - 1);
+    heights[selected] = heights[selected].min(available.max(LINE_HEIGHT));
+    let (mut first, mut last) = (selected, selected);
+    let mut used = heights[selected];
+
+    while last + 1 < heights.len() && used + heights[last + 1] <= available {
+        last += 1;
+        used += heights[last];
+    }
+    while first > 0 && used + heights[first - 1] <= available {
+        first -= 1;
+        used += heights[first];
+    }
+
+    heights.iter()
+        .enumerate()
+        .map(|(index, height)| if (first..=last).contains(&index) {
+            *height
+        } else {
+            0
+        })
+        .collect()
+}
+
+/// Column and row of the cursor in text wrapped to a width the way the
+/// info panel wraps it, with the cursor before the character at an index
+///
+/// Words move to the next line when they don't fit, and characters take
+/// their width in columns, so wide characters take two
+pub fn cursor_position(text: &str, cursor: usize, width: u16) -> (u16, u16) {
+    let width = width.max(1);
+    let chars: Vec<char> = text.chars().collect();
+    let cursor = cursor.min(chars.len());
+
+    // Where each character ends up, by its index
+    let mut placed: Vec<Option<(u16, u16)>> = vec![None; chars.len()];
+    let mut row = 0;
+    for (start, line) in lines(&chars) {
+        let mut col = 0;
+        let mut index = start;
+
+        while index < start + line.len() {
+            let c = chars[index];
+            let char_width = c.width().unwrap_or(0) as u16;
+
+            let word_start = index == start || chars[index - 1].is_whitespace();
+            if word_start && !c.is_whitespace() {
+                // Words that don't fit move to the next line, unless they
+                // don't fit on any line
+                let word_width: u16 = chars[index..start + line.len()].iter()
+                    .take_while(|c| !c.is_whitespace())
+                    .map(|c| c.width().unwrap_or(0) as u16)
+                    .sum();
+                if col > 0 && col + word_width > width && word_width <= width {
+                    row += 1;
+                    col = 0;
+                }
+            }
+
+            if char_width > width {
+                index += 1;
+                continue;
+            }
+            if col + char_width > width {
+                // Whitespace at the end of a line isn't carried over
+                row += 1;
+                col = 0;
+                if c.is_whitespace() {
+                    index += 1;
+                    continue;
+                }
+            }
+
+            placed[index] = Some((col, row));
+            col += char_width;
+            index += 1;
+        }
+
+        row += 1;
+    }
+
+    if let Some(position) = placed.get(cursor).copied().flatten() {
+        return position;
+    }
+
+    // The cursor is after the last character placed before it, on the line
+    // of any newlines in between
+    let before = (0..cursor).rev()
+        .find_map(|index| placed[index].map(|position| (index, position)));
+    let (col, row) = match before {
+        Some((index, (col, row))) => {
+            let newlines = chars[index..cursor].iter()
+                .filter(|c| **c == '\n')
+                .count() as u16;
+            let col = col + chars[index].width().unwrap_or(0) as u16;
+
+            if newlines > 0 {
+                (0, row + newlines)
+            } else {
+                (col, row)
+            }
+        },
+        None => (0, chars[..cursor].iter().filter(|c| **c == '\n').count()
+            as u16),
+    };
+
+    if col >= width {
+        (0, row + 1)
+    } else {
+        (col, row)
+    }
+}
+
+/// The lines of some text, with the index of the first character of each
+fn lines(chars: &[char]) -> Vec<(usize, &[char])> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+
+    for (index, c) in chars.iter().enumerate() {
+        if *c == '\n' {
+            lines.push((start, &chars[start..index]));
+            start = index + 1;
+        }
+    }
+    lines.push((start, &chars[start..]));
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cursor_position,
+        field_heights,
+    };
+
+    #[test]
+    fn fits_every_field_when_there_is_room() {
+        assert_eq!(field_heights(&[false, false], 0, 40, 10, 20), [3, 3]);
+        assert_eq!(field_heights(&[false, true], 0, 40, 10, 20), [3, 8]);
+        assert!(field_heights(&[], 0, 40, 10, 20).is_empty());
+    }
+
+    #[test]
+    fn shrinks_multiline_fields_to_fit() {
+        assert_eq!(field_heights(&[false, true, true], 0, 20, 7, 50),
+            [3, 5, 5]);
+    }
+
+    #[test]
+    fn shows_the_fields_around_the_selected_one_when_they_dont_fit() {
+        let heights = field_heights(&[false; 6], 4, 12, 3, 20);
+
+        assert_eq!(heights, [0, 0, 0, 3, 3, 3]);
+    }
+
+    #[test]
+    fn places_the_cursor_in_wrapped_and_wide_text() {
+        assert_eq!(cursor_position("hello world", 8, 8), (2, 1));
+        assert_eq!(cursor_position("日本語", 2, 10), (4, 0));
+        assert_eq!(cursor_position("ab\ncd", 4, 10), (1, 1));
+    }
+}
@@ -0,0 +1,240 @@
+//! # migration
+//!
+//! The `migration` crate upgrades what's saved in a workspace from the
+//! layouts older versions of the app saved it in. The layout's version is
+//! kept in the workspace and covers every store in it: the request and
+//! template databases, the environments and the request files. Each file is
+//! backed up before it's upgraded.
+
+use std::{
+    error::Error,
+    fs::{
+        self,
+        File,
+        OpenOptions,
+    },
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+use serde_json::{
+    Map,
+    Value,
+};
+
+use super::{
+    environment::Environment,
+    request::Request,
+    storage::{
+        REQUESTS_DIR,
+        UNTRACKED_FIELDS,
+    },
+};
+
+/// Version of the layout requests are saved in by this version of the app
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// File in a workspace holding the version of its layout
+pub const VERSION_FILE: &str = "schema_version";
+
+/// The JSON database of requests, mapping ids to requests
+const DATA_FILE: &str = "data.json";
+
+/// The JSON databases in a workspace, and the kind of item each holds
+const DATABASES: [(&str, Kind); 3] = [
+    (DATA_FILE, Kind::Request),
+    ("templates.json", Kind::Request),
+    ("environments.json", Kind::Environment),
+];
+
+/// Upgrades saved items from one version to the next, for each version
+/// starting at 1
+///
+/// Fields added with `#[serde(default)]` don't need a step, since items are
+/// loaded and saved again in the current layout once the steps have run.
+/// Steps are for changes defaults can't describe, like renamed fields. A step
+/// can run again on items it already upgraded, when a migration that failed
+/// part way is retried, so it should leave them as they are
+const MIGRATIONS: [Migration; 1] = [
+    // Version 2 only added fields to requests
+    Migration {
+        request: unchanged,
+        environment: unchanged,
+    },
+];
+
+/// The changes between two versions to each kind of saved item
+struct Migration {
+    request: fn(&mut Map<String, Value>),
+    environment: fn(&mut Map<String, Value>),
+}
+
+/// A kind of item saved in a workspace
+#[derive(Clone, Copy)]
+enum Kind {
+    Request,
+    Environment,
+}
+
+/// Upgrade what's saved in a workspace to the current layout, if it was
+/// saved by an older version
+///
+/// Each file is copied to `<name>.v<version>.json.bak` first, like
+/// `data.v1.json.bak`. Returns the version the workspace was at
+pub fn migrate(workspace: &Path) -> Result<u32, Box<dyn Error>> {
+    let version = saved_version(workspace)?;
+    if version > SCHEMA_VERSION {
+        return Err(format!("{} was saved by a newer version of Almagro \
+            (schema version {}, this version understands up to {})",
+            workspace.display(), version, SCHEMA_VERSION).into());
+    }
+
+    if version < SCHEMA_VERSION {
+        for (name, kind) in DATABASES {
+            let path = workspace.join(name);
+            if path.exists() {
+                upgrade_file(&path, version, |data| {
+                    let items = data.as_object_mut()
+                        .ok_or("isn't a JSON object")?;
+                    items.values_mut()
+                        .try_for_each(|item| kind.upgrade(item, version))
+                })?;
+            }
+        }
+
+        for path in request_files(&workspace.join(REQUESTS_DIR))? {
+            upgrade_file(&path, version, |req| {
+                Kind::Request.upgrade(req, version)?;
+
+                // Request files leave out the fields that change every run
+                if let Some(fields) = req.as_object_mut() {
+                    for field in UNTRACKED_FIELDS {
+                        fields.remove(field);
+                    }
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    if version != SCHEMA_VERSION || !workspace.join(VERSION_FILE).exists() {
+        fs::write(workspace.join(VERSION_FILE),
+            format!("{}\n", SCHEMA_VERSION))?;
+    }
+
+    Ok(version)
+}
+
+/// Version of the layout a workspace was saved in
+///
+/// Workspaces from before versions were saved are version 1, and new ones
+/// are the current version
+fn saved_version(workspace: &Path) -> Result<u32, Box<dyn Error>> {
+    let version_path = workspace.join(VERSION_FILE);
+
+    if version_path.exists() {
+        let text = fs::read_to_string(&version_path)?;
+        let version = text.trim().parse::<u32>()
+            .map_err(|_| format!("{} doesn't hold a schema version",
+                version_path.display()))?;
+
+        if version == 0 {
+            return Err(format!("{} doesn't hold a schema version",
+                version_path.display()).into());
+        }
+        Ok(version)
+    } else if workspace.join(DATA_FILE).exists() {
+        Ok(1)
+    } else {
+        Ok(SCHEMA_VERSION)
+    }
+}
+
+impl Kind {
+    /// Upgrade a saved item from a version to the current one
+    fn upgrade(self, item: &mut Value, version: u32)
+        -> Result<(), Box<dyn Error>> {
+
+        if let Some(fields) = item.as_object_mut() {
+            for migration in MIGRATIONS[version as usize - 1..].iter() {
+                match self {
+                    Kind::Request => (migration.request)(fields),
+                    Kind::Environment => (migration.environment)(fields),
+                }
+            }
+        }
+
+        match self {
+            Kind::Request => resave::<Request>(item),
+            Kind::Environment => resave::<Environment>(item),
+        }
+    }
+}
+
+/// Load an item and save it again, writing out the fields added since it was
+/// saved with their defaults
+fn resave<T: Serialize + DeserializeOwned>(item: &mut Value)
+    -> Result<(), Box<dyn Error>> {
+
+    let loaded: T = serde_json::from_value(item.take())?;
+    *item = serde_json::to_value(&loaded)?;
+    Ok(())
+}
+
+/// For kinds of items a version didn't change
+fn unchanged(_: &mut Map<String, Value>) {}
+
+/// Back up a JSON file, and write it again after upgrading its contents
+///
+/// A backup left by an earlier migration that failed is kept, since the file
+/// may have been upgraded since it was made
+fn upgrade_file(path: &Path, version: u32,
+    upgrade: impl FnOnce(&mut Value) -> Result<(), Box<dyn Error>>)
+    -> Result<(), Box<dyn Error>> {
+
+    let backup_path = path.with_extension(format!("v{}.json.bak", version));
+    match OpenOptions::new().write(true).create_new(true).open(&backup_path) {
+        Ok(mut backup) => {
+            io::copy(&mut File::open(path)?, &mut backup)?;
+        },
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => (),
+        Err(e) => return Err(e.into()),
+    }
+
+    let mut value: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    upgrade(&mut value)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    fs::write(path, serde_json::to_string_pretty(&value)? + "\n")?;
+    Ok(())
+}
+
+/// Paths of the request files in a directory and its folders
+fn request_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                files.push(path);
+            }
+        }
+    }
+
+    Ok(files)
+}
@@ -4,8 +4,10 @@
 //! run its functionality.
 
 use std::{
-    collections::BTreeMap,
-    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
 };
 
 use crossterm::event::{
@@ -13,27 +15,90 @@ use crossterm::event::{
     KeyEvent,
     Event, KeyModifiers,
 };
-use home;
-use jfs::Store;
-use reqwest::Method;
 use tui_input::{
     backend::crossterm as input_backend,
-    Input
+    Input,
+    InputRequest,
 };
 
-use self::request::{
-    REQ_FIELD_COUNT,
-    Request,
+use self::{
+    command::CommandLine,
+    editor::EditTarget,
+    config::{
+        Action,
+        Config,
+        Key,
+        KeyMatch,
+    },
+    environment::{
+        Environment,
+        Variables,
+    },
+    export::HAR_EXPORT_PATH,
+    history::{
+        Change,
+        History,
+        Snapshot,
+    },
+    http_file::HttpFile,
+    mouse::Areas,
+    picker::{
+        Picker,
+        PickerAction,
+    },
+    request::{
+        BodyMode,
+        METHODS,
+        RAW_CONTENT_TYPES,
+        ReqField,
+        Request,
+    },
+    storage::Storage,
+    tabs::Tabs,
+    validation::Spec,
+    websocket::WEBSOCKET_TYPE,
 };
 
+pub mod command;
+pub mod completion;
+pub mod config;
+pub mod download;
+pub mod editor;
+pub mod environment;
+pub mod export;
+pub mod graphql;
+pub mod har;
+pub mod help;
+pub mod history;
+pub mod http_file;
+pub mod import;
+pub mod layout;
+pub mod migration;
+pub mod mouse;
+pub mod openapi;
+pub mod picker;
 pub mod request;
+pub mod schema;
+pub mod sse;
+pub mod storage;
+pub mod tabs;
+pub mod template;
+#[cfg(test)]
+mod test_util;
+pub mod theme;
 pub mod ui;
+pub mod validation;
+pub mod websocket;
+pub mod workspace;
 
 /// Are we inserting text or navigating the UI?
 #[derive(PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 enum InputStatus {
     INSERT,
     NORMAL,
+    // Typing a `:` command
+    COMMAND,
 }
 
 /// Are we navigating the request list or the info panel
@@ -46,13 +111,49 @@ pub enum SelElement {
 /// Contains the data and methods to run the app
 pub struct App {
     pub is_running: bool,
+    // Directory the requests and environments are stored in
+    pub workspace: PathBuf,
     // Index of the current selected request
     pub req_index: usize,
-    pub db: Store,
+    pub db: Box<dyn Storage<Request>>,
     pub requests: Vec<Request>,
-    // Inputs to change request info
+    // Requests new requests can start from
+    pub template_db: Box<dyn Storage<Request>>,
+    pub templates: Vec<Request>,
+    // Environments of variables, and the index of the active one if any
+    pub env_db: Box<dyn Storage<Environment>>,
+    pub environments: Vec<Environment>,
+    pub env_index: Option<usize>,
+    // OpenAPI specs responses are validated against
+    pub specs: Vec<Spec>,
+    // `.http` files opened for editing
+    pub http_files: Vec<HttpFile>,
+    // Message shown at the bottom of the screen, like errors saving requests
+    pub status_line: String,
+    // Changes to the request list that can be undone
+    pub history: History,
+    // Popup list shown over the UI to choose from, if any
+    pub picker: Option<Picker>,
+    // The `:` command being typed, and earlier commands
+    pub command_line: CommandLine,
+    // Whether the keys for the current mode and pane are shown
+    pub show_help: bool,
+    // Where parts of the UI were last drawn, for mouse clicks
+    pub areas: Areas,
+    // Key bindings, layout and request defaults from the config file
+    pub config: Config,
+    // Keys pressed so far of a binding with several keys, like `g t`
+    pub pending_keys: Vec<Key>,
+    // Requests open in tabs, and where each tab was left
+    pub tabs: Tabs,
+    // Field or request to open in the editor before the UI is drawn again
+    pub edit_target: Option<EditTarget>,
+    // Inputs to change request info, and the request field each one edits
     pub inputs: Vec<Input>,
+    pub fields: Vec<ReqField>,
     pub input_index: usize,
+    // GraphQL names that complete the word being typed in the query editor
+    pub completions: Vec<String>,
     // Offset for selected text box
     pub text_offset: (u16, u16),
     input_status: InputStatus,
@@ -61,35 +162,89 @@ pub struct App {
 
 impl App {
     /// Construct a new App object
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let mut db_config = jfs::Config::default();
-        db_config.single = true;
-
+    ///
+    /// Takes in the workspace directory given on the command line, if any,
+    /// and finds one otherwise
+    pub fn new(workspace_dir: Option<&Path>)
+        -> Result<Self, Box<dyn std::error::Error>> {
 
         // Create/get path to saved requests database
-        let home_dir = home::home_dir()
-            .ok_or("Couldn't get user home directory")?;
-        fs::create_dir_all(home_dir.join(".almagro"))?;
-        let data_path = home_dir.join(".almagro").join("data");
+        let workspace = workspace::find(workspace_dir)?;
+        migration::migrate(&workspace)?;
+        let db = storage::open_requests(&workspace)?;
+        let template_db = storage::open_templates(&workspace)?;
+        let env_db = storage::open_environments(&workspace)?;
+
+        let mut app = Self::with_storage(workspace, db, template_db, env_db)?;
+
+        // Invalid settings are shown until the first key press
+        let (config, errors) = Config::load(&workspace::global()?);
+        app.config = config;
+        if !errors.is_empty() {
+            app.status_line = format!("{}: {}", config::CONFIG_FILE,
+                errors.join("; "));
+        }
+
+        Ok(app)
+    }
+
+    /// Construct an App object that saves requests, templates and
+    /// environments to the given storage
+    pub fn with_storage(workspace: PathBuf, db: Box<dyn Storage<Request>>,
+        template_db: Box<dyn Storage<Request>>,
+        env_db: Box<dyn Storage<Environment>>)
+        -> Result<Self, Box<dyn std::error::Error>> {
 
         let mut app = Self {
             is_running: true,
+            workspace,
             req_index: 0,
-            db: Store::new_with_cfg(data_path, db_config).unwrap(),
+            db,
             requests: Vec::new(),
+            template_db,
+            templates: Vec::new(),
+            env_db,
+            environments: Vec::new(),
+            env_index: None,
+            specs: Vec::new(),
+            http_files: Vec::new(),
+            status_line: String::new(),
+            history: History::default(),
+            picker: None,
+            command_line: CommandLine::default(),
+            show_help: false,
+            areas: Areas::default(),
+            config: Config::default(),
+            pending_keys: Vec::new(),
+            tabs: Tabs::default(),
+            edit_target: None,
             input_status: InputStatus::NORMAL,
             sel_element: SelElement::LIST,
-            inputs: vec!["".into(); REQ_FIELD_COUNT],
+            inputs: Vec::new(),
+            fields: Vec::new(),
             input_index: 0,
+            completions: Vec::new(),
             text_offset: (0, 0),
         };
 
         // Load all requests from the database
-        let req_btree: BTreeMap<String, Request> = app.db.all()?;
+        let req_btree = app.db.all()?;
         for (_, req) in req_btree {
             app.requests.push(req);
         }
 
+        // Load all templates from their database
+        let template_btree = app.template_db.all()?;
+        for (_, template) in template_btree {
+            app.templates.push(template);
+        }
+
+        // Load all environments from their database
+        let env_btree = app.env_db.all()?;
+        for (_, env) in env_btree {
+            app.environments.push(env);
+        }
+
         app.update_inputs();
 
         return Ok(app);
@@ -97,16 +252,34 @@ impl App {
 
     /// Handle key events in insert mode
     fn insert_mode_keys(&mut self, modifiers: KeyModifiers, code: KeyCode) {
-        match code {
-            KeyCode::Esc => {
+        self.completions.clear();
+
+        match self.config.keys.action(true, modifiers, code) {
+            Some(Action::Cancel) => {
                 self.input_status = InputStatus::NORMAL;
                 self.update_inputs();
             },
-            KeyCode::Enter => {
+            // Insert a newline in fields that allow multiple lines
+            Some(Action::Newline)
+                if self.fields[self.input_index].is_multiline() => {
+
+                self.inputs[self.input_index]
+                    .handle(InputRequest::InsertChar('\n'));
+            },
+            Some(Action::Complete) => self.complete_gql_name(),
+            Some(Action::Help) => self.show_help = true,
+            Some(Action::Submit) | Some(Action::Newline) => {
                 if self.requests.len() > 0 {
                     self.update_req_info();
                     self.save_current_request();
                     self.input_status = InputStatus::NORMAL;
+
+                    // Send the composed WebSocket message straight away
+                    if self.fields[self.input_index] == ReqField::Message {
+                        let variables = self.variables();
+                        self.requests[self.req_index]
+                            .run_websocket(&variables);
+                    }
                 }
             },
             _ => {
@@ -121,10 +294,31 @@ impl App {
     }
 
     /// Handle key events in normal mode
-    fn normal_mode_keys(&mut self, _modifiers: KeyModifiers, code: KeyCode) {
-        match code {
-            KeyCode::Esc | KeyCode::Char('q') => self.is_running = false,
-            KeyCode::Char('j') => {
+    fn normal_mode_keys(&mut self, modifiers: KeyModifiers, code: KeyCode) {
+        // Keys of a binding with several keys wait for the rest, and keys
+        // that don't finish one are dropped
+        let key = Key::pressed(modifiers, code);
+        let action = match self.config.keys.lookup(false, &self.pending_keys,
+            key) {
+
+            KeyMatch::Pending => {
+                self.pending_keys.push(key);
+                return;
+            },
+            KeyMatch::Action(action) => {
+                self.pending_keys.clear();
+                Some(action)
+            },
+            KeyMatch::None if !self.pending_keys.is_empty() => {
+                self.pending_keys.clear();
+                return;
+            },
+            KeyMatch::None => None,
+        };
+
+        match action {
+            Some(Action::Quit) => self.is_running = false,
+            Some(Action::Down) => {
                 match self.sel_element {
                     SelElement::LIST => {
                         self.req_index = (self.req_index + 1)
@@ -139,27 +333,31 @@ impl App {
                     }
                 }
             },
-            KeyCode::Down => {
+            Some(Action::ScrollDown) => {
                 if self.sel_element == SelElement::INFOPANEL {
                     // Scroll text
                     self.text_offset.0 += 1;
                 }
             },
             // Allow swapping with next request in list
-            KeyCode::Char('J') => {
+            Some(Action::MoveDown) => {
                 if self.sel_element == SelElement::LIST 
                     && self.req_index < self.requests.len() - 1 {
 
                     self.requests
                         .swap(self.req_index, self.req_index + 1);
+                    self.tabs_swapped(self.req_index, self.req_index + 1);
+                    self.history.record(Change::Swap(self.req_index,
+                        self.req_index + 1));
                     self.req_index += 1;
                 }
             },
-            KeyCode::Char('k') => {
+            Some(Action::Up) => {
                 match self.sel_element {
                     SelElement::LIST => {
                         if self.req_index == 0 {
                             self.req_index = self.requests.len() - 1;
+                            self.update_inputs();
                             return;
                         }
                         self.req_index -= 1;
@@ -177,7 +375,7 @@ impl App {
                 }
             },
             // Scroll text
-            KeyCode::Up => {
+            Some(Action::ScrollUp) => {
                 if self.sel_element == SelElement::INFOPANEL {
                     if self.text_offset.0 > 0 {
                         self.text_offset.0 -= 1;
@@ -185,21 +383,28 @@ impl App {
                 }
             },
             // Allow swapping with previous request in list
-            KeyCode::Char('K') => {
+            Some(Action::MoveUp) => {
                 if self.sel_element == SelElement::LIST
                     && self.req_index > 0 {
 
                     self.requests
                         .swap(self.req_index, self.req_index - 1);
+                    self.tabs_swapped(self.req_index, self.req_index - 1);
+                    self.history.record(Change::Swap(self.req_index,
+                        self.req_index - 1));
                     self.req_index -= 1;
                 }
             },
-            KeyCode::Char('i') => {
+            // The method is chosen from a list rather than typed
+            Some(Action::Insert) if self.sel_element == SelElement::INFOPANEL
+                && self.fields[self.input_index] == ReqField::Method
+                && !self.requests.is_empty() => self.open_method_picker(),
+            Some(Action::Insert) => {
                 self.input_status = InputStatus::INSERT;
                 self.sel_element = SelElement::INFOPANEL;
             },
             // Select list or info panel
-            KeyCode::Char('h') | KeyCode::Char('l') => self.sel_element
+            Some(Action::SwitchPanel) => self.sel_element
                     = match self.sel_element {
                     SelElement::LIST => SelElement::INFOPANEL,
                     SelElement::INFOPANEL => {
@@ -208,45 +413,109 @@ impl App {
                             SelElement::LIST
                     },
             },
-            KeyCode::Left => if self.text_offset.1 > 0 {
-                self.text_offset.1 -= 1;
+            Some(Action::ScrollLeft) =>
+                self.text_offset.1 = self.text_offset.1.saturating_sub(1),
+            Some(Action::ScrollRight) => self.text_offset.1 += 1,
+            Some(Action::Run) => {
+                let variables = self.variables();
+
+                if let Some(cur_req) = self.requests.get_mut(self.req_index) {
+                    if cur_req.is_websocket() {
+                        cur_req.run_websocket(&variables);
+                    } else {
+                        cur_req.run_req(&variables, &self.config);
+                    }
+                }
             },
-            KeyCode::Right => self.text_offset.1 += 1,
-            KeyCode::Enter => if self.requests.len() > 0 {
-                self.requests[self.req_index].run_req();
+            // Switch to the next environment, or to none after the last one
+            Some(Action::NextEnvironment) => self.env_index
+                = match self.env_index {
+
+                None if !self.environments.is_empty() => Some(0),
+                Some(index) if index + 1 < self.environments.len() =>
+                    Some(index + 1),
+                _ => None,
             },
-            KeyCode::Char('n') => {
-                // Create a new request
-                let new_req = Request::new(format!("Request #{}",
-                    self.requests.len() + 1));
+            // Close the selected request's WebSocket session or stop its event
+            // stream
+            Some(Action::Close) if !self.requests.is_empty() =>
+                self.requests[self.req_index].close_connection(),
+            // Mark the selected request to be exported
+            Some(Action::Mark) => if let Some(cur_req)
+                = self.requests.get_mut(self.req_index) {
 
-                self.requests.push(new_req);
-                self.req_index = self.requests.len() - 1;
-                self.update_inputs();
+                cur_req.marked = !cur_req.marked;
+            },
+            // Export the marked requests, or the selected one, as HAR
+            Some(Action::Export) if !self.requests.is_empty() => {
+                let path = Path::new(HAR_EXPORT_PATH);
+                self.status_line = match self.export_har(path) {
+                    Ok(count) => format!("Exported {} requests to {}", count,
+                        path.display()),
+                    Err(e) => format!("{}", e),
+                };
+            },
+            // Save the full body of the last response to a file
+            Some(Action::SaveBody) if !self.requests.is_empty() => {
+                let variables = self.variables();
+                let cur_req = &self.requests[self.req_index];
+                self.status_line = match cur_req.save_body(&variables) {
+                    Ok(path) => format!("Saved body to {}", path.display()),
+                    Err(e) => format!("{}", e),
+                };
+            },
+            // Introspect the GraphQL schema for query completion
+            Some(Action::LoadSchema) if !self.requests.is_empty() => {
+                let variables = self.variables();
+                self.requests[self.req_index].load_gql_schema(&variables,
+                    &self.config);
+            },
+            // Cycle through the choices of the body mode and content type
+            Some(Action::CycleChoice) if self.sel_element
+                == SelElement::INFOPANEL && !self.requests.is_empty() =>
+                self.cycle_choice(),
+            // Create a new request, from a template if there are any
+            Some(Action::New) => self.new_request(),
+            // Copy the selected request to just below it
+            Some(Action::Duplicate) if !self.requests.is_empty() => {
+                let mut copy = self.requests[self.req_index].duplicate();
+                copy.name = format!("{} (copy)", copy.name);
+                let index = self.req_index + 1;
 
-                // Save the new request
-                self.save_current_request();
+                self.insert_request(index, copy);
+                self.history.record(Change::Create(index,
+                    Snapshot::of(&self.requests[index])));
             },
-            KeyCode::Char('x') => {
-                // Delete a request
-                if self.requests.len() > 0 {
-                    let cur_id = &self.requests[self.req_index].db_id;
-                    // Delete the request from the database
-                    self.db.delete(&cur_id)
-                        .expect("Couldn't delete request from the database");
-                    self.requests.remove(self.req_index);
-                    self.update_inputs();
-
-                    if self.req_index > 0 { self.req_index -= 1 }
-                }
-            }
+            // Save the selected request as a template for new requests
+            Some(Action::SaveTemplate) => self.save_template(),
+            Some(Action::Delete) => self.delete_request(),
+            Some(Action::Undo) => self.undo(),
+            Some(Action::Redo) => self.redo(),
+            Some(Action::Command) => self.open_command_line(),
+            Some(Action::OpenTab) => self.open_tab(),
+            Some(Action::NextTab) => self.switch_tab(true),
+            Some(Action::PreviousTab) => self.switch_tab(false),
+            Some(Action::Edit) => self.open_editor(),
+            Some(Action::Help) => self.show_help = true,
             _ => (),
         }
     }
 
     /// Handle key events in the app
     pub fn handle_keys(&mut self, event: Event) {
+        // Messages stay in the status line until the next key press
+        if let Event::Key(_) = event {
+            self.status_line.clear();
+        }
+
         match event {
+            // Any key closes the help
+            Event::Key(_) if self.show_help => self.show_help = false,
+            Event::Key(KeyEvent {
+                modifiers,
+                code,
+            }) if self.picker.is_some() => self.picker_keys(modifiers, code),
+            Event::Mouse(mouse) => self.handle_mouse(mouse),
             Event::Key(KeyEvent {
                 modifiers,
                 code,
@@ -256,57 +525,310 @@ impl App {
                         self.insert_mode_keys(modifiers, code),
                     InputStatus::NORMAL =>
                         self.normal_mode_keys(modifiers, code),
+                    InputStatus::COMMAND =>
+                        self.command_mode_keys(modifiers, code),
                 },
             _ => (),
         };
     }
 
+    /// Update the app state that changes without key events, like frames
+    /// received by WebSocket sessions and event streams
+    pub fn update(&mut self) {
+        for req in self.requests.iter_mut() {
+            req.update_connections();
+        }
+
+        self.check_responses();
+    }
+
+    /// Get the variables of the active environment, and of the `.http` file
+    /// the selected request is from
+    pub fn variables(&self) -> Variables {
+        self.request_variables(self.req_index)
+    }
+
+    /// Get the variables of the active environment, and of the `.http` file
+    /// the request at an index is from
+    ///
+    /// Variables defined in the file take precedence
+    pub fn request_variables(&self, index: usize) -> Variables {
+        let mut variables = self.env_index
+            .and_then(|index| self.environments.get(index))
+            .map_or(Variables::new(), |env| env.variables.clone());
+
+        let http_block = self.requests.get(index)
+            .and_then(|req| req.http_block);
+        if let Some((file_index, _)) = http_block {
+            variables.extend(self.http_files[file_index].variables.clone());
+        }
+
+        variables
+    }
+
     /// Update the text input boxes to hold the values of the current request
     fn update_inputs(&mut self) {
-        if let Some(cur_req) = self.requests.get(self.req_index) {
-            (self.inputs[0], self.inputs[1], self.inputs[2], self.inputs[3])
-                = (cur_req.name.clone().into(),
-                    cur_req.req_type.as_str().into(),
-                    cur_req.url.clone().into(),
-                    cur_req.body.clone().into());
-        } else {
-            for input in self.inputs.iter_mut() {
-                input.reset();
-            }
+        match self.requests.get(self.req_index) {
+            Some(cur_req) => {
+                self.fields = cur_req.fields();
+                self.inputs = self.fields.iter()
+                    .map(|field| cur_req.field_text(*field).into())
+                    .collect();
+            },
+            None => {
+                self.fields = ReqField::for_mode(Default::default());
+                self.inputs = vec!["".into(); self.fields.len()];
+            },
+        }
+
+        // The current request may have fewer fields than the last one
+        if self.input_index >= self.inputs.len() {
+            self.input_index = 0;
         }
     }
 
     /// Update data in a request with input field data in the UI
     fn update_req_info(&mut self) {
         let input_text = self.inputs[self.input_index].value();
+        let field = self.fields[self.input_index];
+        let cur_req = &mut self.requests[self.req_index];
 
-        match self.input_index {
-            0 => self.requests[self.req_index].name = input_text.to_string(),
-            1 => if let Ok(req_type) = Method::from_bytes(input_text.as_bytes()) {
-                self.requests[self.req_index].req_type = req_type.to_string();
-                self.update_inputs();
-            } else {
-                self.update_inputs();
+        let before = cur_req.field_text(field);
+        cur_req.set_field_text(field, input_text);
+        let after = cur_req.field_text(field);
+
+        if before != after {
+            self.history.record(Change::Edit {
+                index: self.req_index,
+                field,
+                before,
+                after,
+            });
+        } else if field == ReqField::Method
+            && !input_text.trim().eq_ignore_ascii_case(&after) {
+
+            self.status_line = format!("\"{}\" isn't a valid method",
+                input_text);
+        }
+
+        // Revert invalid text, and show the fields of a new body mode
+        self.update_inputs();
+    }
+
+    /// Complete the GraphQL type or field name being typed in the query editor
+    ///
+    /// Inserts the part shared by every matching name, and lists the matches
+    /// if there are several
+    fn complete_gql_name(&mut self) {
+        let cur_req = match self.requests.get(self.req_index) {
+            Some(cur_req) if cur_req.body_mode == BodyMode::GraphQl
+                && self.fields[self.input_index] == ReqField::Body => cur_req,
+            _ => return,
+        };
+
+        let input = &mut self.inputs[self.input_index];
+        let (start, matches) = graphql::complete(input.value(),
+            input.cursor(), &cur_req.gql_schema);
+        let typed = input.cursor() - start;
+
+        for c in completion::common_prefix(&matches).chars().skip(typed) {
+            input.handle(InputRequest::InsertChar(c));
+        }
+
+        if matches.len() > 1 {
+            self.completions = matches.into_iter().cloned().collect();
+        }
+    }
+
+    /// Select the next choice for the current request field, if the field
+    /// has a fixed set of choices
+    fn cycle_choice(&mut self) {
+        let field = self.fields[self.input_index];
+        let cur_req = &mut self.requests[self.req_index];
+        let before = cur_req.field_text(field);
+
+        match field {
+            ReqField::BodyMode => cur_req.body_mode = cur_req.body_mode.next(),
+            ReqField::ContentType => {
+                let index = RAW_CONTENT_TYPES.iter()
+                    .position(|content_type| *content_type == cur_req.content_type)
+                    .map_or(0, |index| (index + 1) % RAW_CONTENT_TYPES.len());
+                cur_req.content_type = RAW_CONTENT_TYPES[index].to_string();
             },
-            2 => self.requests[self.req_index].url = input_text.to_string(),
-            3 => self.requests[self.req_index].body = input_text.to_string(),
-            _ => (),
+            _ => return,
+        };
+
+        self.history.record(Change::Edit {
+            index: self.req_index,
+            field,
+            before,
+            after: cur_req.field_text(field),
+        });
+        self.update_inputs();
+        self.save_current_request();
+    }
+
+    /// Open the picker of methods for the selected request, with its
+    /// method highlighted
+    fn open_method_picker(&mut self) {
+        let items: Vec<String> = METHODS.iter()
+            .chain(std::iter::once(&WEBSOCKET_TYPE))
+            .map(|method| method.to_string())
+            .chain(std::iter::once(String::from("Custom method")))
+            .collect();
+        let current = &self.requests[self.req_index].req_type;
+
+        let mut picker = Picker::new("Request Type", items,
+            PickerAction::Method);
+        picker.index = picker.items.iter()
+            .position(|item| item == current)
+            .unwrap_or(picker.items.len() - 1);
+        self.picker = Some(picker);
+    }
+
+    /// Set the method of the selected request to the one chosen at an index
+    /// of the method picker, or start typing a custom one after the last
+    fn choose_method(&mut self, index: usize) {
+        let method = match METHODS.get(index) {
+            Some(method) => *method,
+            None if index == METHODS.len() => WEBSOCKET_TYPE,
+            None => {
+                self.input_status = InputStatus::INSERT;
+                return;
+            },
+        };
+        let cur_req = match self.requests.get_mut(self.req_index) {
+            Some(cur_req) => cur_req,
+            None => return,
+        };
+
+        let before = cur_req.field_text(ReqField::Method);
+        cur_req.set_field_text(ReqField::Method, method);
+        if before != method {
+            self.history.record(Change::Edit {
+                index: self.req_index,
+                field: ReqField::Method,
+                before,
+                after: method.to_string(),
+            });
+        }
+        self.update_inputs();
+        self.save_current_request();
+    }
+
+    /// Add a request to the request list at an index, select it and save it
+    ///
+    /// Requests from a `.http` file are put back in the file
+    fn insert_request(&mut self, index: usize, req: Request) {
+        if let Some((file_index, id)) = req.http_block {
+            self.http_files[file_index].restore(id);
+        }
+
+        self.requests.insert(index, req);
+        self.tabs_inserted(index);
+        self.req_index = index;
+        self.update_inputs();
+        self.save_request(index);
+    }
+
+    /// Remove the request at an index from the request list, deleting it
+    /// from the database or the `.http` file it's from
+    ///
+    /// Errors are shown in the status line. Returns the removed request
+    fn remove_request(&mut self, index: usize) -> Request {
+        let req = self.requests.remove(index);
+        self.tabs_removed(index);
+
+        let result = match req.http_block {
+            Some((file_index, id)) => {
+                self.http_files[file_index].remove(id);
+                self.write_http_file(file_index)
+            },
+            None => self.db.delete(&req.db_id)
+                .map_err(|e| format!("Couldn't delete request from the \
+                    database: {}", e).into()),
         };
+        if let Err(e) = result {
+            self.status_line = format!("{}", e);
+        }
+
+        if self.req_index > 0 { self.req_index -= 1 }
+        self.update_inputs();
+
+        req
+    }
+
+    /// Delete the selected request, so it can be undone
+    fn delete_request(&mut self) {
+        if !self.requests.is_empty() {
+            let index = self.req_index;
+            let cur_req = self.remove_request(index);
+            self.history.record(Change::Delete(index,
+                Snapshot::of(&cur_req)));
+        }
     }
 
     /// Save the currently selected request to the database
     fn save_current_request(&mut self) {
-        let mut cur_req = &mut self.requests[self.req_index];
-
-        // With this library, you must save once to get the ID, then I store
-        // that ID with the request and save it again to preserve it in the
-        // database
-        if cur_req.db_id.is_empty() {
-            cur_req.db_id = self.db.save(cur_req)
-                .expect("Couldn't save request to database");
+        self.save_request(self.req_index);
+    }
+
+    /// Save the request at an index in the request list to the database, or
+    /// to the `.http` file it was opened from
+    ///
+    /// Errors are shown in the status line
+    fn save_request(&mut self, index: usize) {
+        if let Some((file_index, _)) = self.requests[index].http_block {
+            if let Err(e) = self.write_http_file(file_index) {
+                self.status_line = format!("Couldn't save {}: {}",
+                    self.http_files[file_index].path.display(), e);
+            }
+            return;
+        }
+
+        let cur_req = &mut self.requests[index];
+
+        // With the JSON database, you must save once to get the ID, then I
+        // store that ID with the request and save it again to preserve it in
+        // the database. Request files are named after the request, so their
+        // ID changes when it's renamed
+        let saved = if cur_req.db_id.is_empty() {
+            self.db.save(cur_req)
+                .and_then(|id| {
+                    cur_req.db_id = id;
+                    self.db.save(cur_req)
+                })
+        } else {
+            self.db.save(cur_req)
+        };
+
+        match saved {
+            Ok(id) => cur_req.db_id = id,
+            Err(e) => self.status_line = format!("Couldn't save request to \
+                the database: {}", e),
         }
+    }
 
-        self.db.save_with_id(cur_req, &cur_req.db_id)
-            .expect("Couldn't save request to database");
+    /// Save the environment at an index in the environment list to its
+    /// database
+    ///
+    /// Errors are shown in the status line
+    fn save_environment(&mut self, index: usize) {
+        let env = &mut self.environments[index];
+
+        let saved = if env.db_id.is_empty() {
+            self.env_db.save(env)
+                .and_then(|id| {
+                    env.db_id = id;
+                    self.env_db.save(env)
+                })
+        } else {
+            self.env_db.save(env)
+        };
+
+        if let Err(e) = saved {
+            self.status_line = format!("Couldn't save environment to the \
+                database: {}", e);
+        }
     }
 }
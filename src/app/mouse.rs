@@ -0,0 +1,139 @@
+//! # mouse
+//!
+//! `mouse` handles mouse events: clicking requests in the list and fields in
+//! the info panel, scrolling the list and the response with the wheel, and
+//! dragging the split between the list and the info panel. The areas clicked
+//! are remembered from the last time the UI was drawn.
+
+use crossterm::event::{
+    MouseButton,
+    MouseEvent,
+    MouseEventKind,
+};
+use tui::layout::Rect;
+
+use super::{
+    layout::NARROW_WIDTH,
+    App,
+    InputStatus,
+    SelElement,
+};
+
+/// Lines scrolled by each turn of the mouse wheel
+const SCROLL_LINES: u16 = 3;
+
+/// Where parts of the UI were last drawn
+#[derive(Default)]
+pub struct Areas {
+    // The request list and info panel together
+    pub main: Rect,
+    pub list: Rect,
+    pub fields: Vec<Rect>,
+    pub response: Rect,
+    // Index of the first request shown in the list
+    pub list_offset: usize,
+    // Whether the split between the list and the info panel is being dragged
+    dragging_split: bool,
+}
+
+/// Is a position inside an area?
+fn contains(area: Rect, column: u16, row: u16) -> bool {
+    column >= area.x && column < area.x + area.width
+        && row >= area.y && row < area.y + area.height
+}
+
+impl App {
+    /// Handle mouse events in the app
+    ///
+    /// Clicks only select things in normal mode, so edits aren't lost
+    pub fn handle_mouse(&mut self, event: MouseEvent) {
+        let (column, row) = (event.column, event.row);
+
+        // Popups close when clicked
+        if self.show_help || self.picker.is_some() {
+            if let MouseEventKind::Down(_) = event.kind {
+                self.show_help = false;
+                self.picker = None;
+            }
+            return;
+        }
+
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                // The split is the list's right border, when both panes are
+                // shown
+                let split = self.areas.list.x + self.areas.list.width;
+                if self.areas.main.width >= NARROW_WIDTH
+                    && (column + 1 == split || column == split) {
+
+                    self.areas.dragging_split = true;
+                } else if self.input_status == InputStatus::NORMAL {
+                    self.click(column, row);
+                }
+            },
+            MouseEventKind::Drag(MouseButton::Left)
+                if self.areas.dragging_split => {
+
+                let main = self.areas.main;
+                if main.width > 0 {
+                    let width = column.saturating_sub(main.x) as u32 * 100
+                        / main.width as u32;
+                    self.config.list_width = (width as u16).clamp(10, 90);
+                }
+            },
+            MouseEventKind::Up(_) => self.areas.dragging_split = false,
+            MouseEventKind::ScrollDown => self.scroll(column, row, true),
+            MouseEventKind::ScrollUp => self.scroll(column, row, false),
+            _ => (),
+        }
+    }
+
+    /// Select the request or field clicked
+    fn click(&mut self, column: u16, row: u16) {
+        let list = self.areas.list;
+        if contains(list, column, row) {
+            self.sel_element = SelElement::LIST;
+
+            // Rows inside the borders are requests, from the first shown
+            if row > list.y && row + 1 < list.y + list.height {
+                let index = (row - list.y - 1) as usize
+                    + self.areas.list_offset;
+                if index < self.requests.len() {
+                    self.req_index = index;
+                    self.update_inputs();
+                }
+            }
+            return;
+        }
+
+        let clicked = self.areas.fields.iter()
+            .position(|area| contains(*area, column, row));
+        if let Some(index) = clicked {
+            self.sel_element = SelElement::INFOPANEL;
+            if index != self.input_index {
+                self.input_index = index;
+                self.text_offset = (0, 0);
+            }
+        }
+    }
+
+    /// Scroll the list or the response under the mouse
+    fn scroll(&mut self, column: u16, row: u16, down: bool) {
+        if contains(self.areas.list, column, row) && !self.requests.is_empty()
+            && self.input_status == InputStatus::NORMAL {
+
+            self.req_index = if down {
+                (self.req_index + 1).min(self.requests.len() - 1)
+            } else {
+                self.req_index.saturating_sub(1)
+            };
+            self.update_inputs();
+        } else if contains(self.areas.response, column, row) {
+            self.text_offset.0 = if down {
+                self.text_offset.0.saturating_add(SCROLL_LINES)
+            } else {
+                self.text_offset.0.saturating_sub(SCROLL_LINES)
+            };
+        }
+    }
+}
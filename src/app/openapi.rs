@@ -0,0 +1,842 @@
+//! # openapi
+//!
+//! The `openapi` crate contains methods to turn OpenAPI 3 and Swagger 2 specs
+//! into requests, with an environment for each server in the spec, and to
+//! validate responses against the operations in a spec.
+
+use std::{
+    fs,
+    path::Path,
+};
+
+use serde_json::{
+    json,
+    Map,
+    Value,
+};
+
+use super::{
+    download::{
+        format_size,
+        PREVIEW_LEN,
+    },
+    environment::Environment,
+    import::Collection,
+    request::{
+        BodyMode,
+        FormField,
+        Request,
+    },
+    schema::{
+        self,
+        Violation,
+    },
+};
+
+/// HTTP methods that can have operations in a spec's path items
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Name of the variable holding a server's url in generated environments
+pub const BASE_URL_VAR: &str = "baseUrl";
+
+// Most refs followed in a row, when resolving or building examples
+const MAX_REF_DEPTH: usize = 8;
+
+/// Read a JSON or YAML file into a JSON value
+pub fn load_document(path: &Path) -> Result<Value, Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path)?;
+
+    match serde_json::from_str(&text) {
+        Ok(value) => Ok(value),
+        Err(_) => Ok(serde_yaml::from_str(&text)?),
+    }
+}
+
+/// Is the document an OpenAPI 3 or Swagger 2 spec?
+pub fn is_spec(doc: &Value) -> bool {
+    doc.get("openapi").is_some() || doc.get("swagger").is_some()
+}
+
+/// Follow a local `$ref` in the spec, returning the value it points to
+///
+/// Values without a `$ref` are returned as they are
+pub fn resolve<'a>(spec: &'a Value, value: &'a Value) -> &'a Value {
+    let mut value = value;
+
+    // Limit the number of refs followed in case they form a loop
+    for _ in 0..MAX_REF_DEPTH {
+        match value.get("$ref").and_then(|r| r.as_str()) {
+            Some(reference) => match reference.strip_prefix('#')
+                .and_then(|pointer| spec.pointer(pointer)) {
+
+                Some(target) => value = target,
+                None => return &Value::Null,
+            },
+            None => break,
+        }
+    }
+
+    value
+}
+
+/// Generate a request for each operation in a spec, in a folder named after
+/// the spec's title
+pub fn to_collection(spec: &Value)
+    -> Result<Collection, Box<dyn std::error::Error>> {
+
+    let paths = spec["paths"].as_object()
+        .ok_or("The spec doesn't have any paths")?;
+    let title = spec["info"]["title"].as_str()
+        .unwrap_or("OpenAPI")
+        .to_string();
+    let is_swagger = spec.get("swagger").is_some();
+
+    let mut requests = Vec::new();
+    for (path, item) in paths {
+        let item = resolve(spec, item);
+        let shared_params = item["parameters"].as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        for method in METHODS {
+            let operation = match item.get(method) {
+                Some(operation) => operation,
+                None => continue,
+            };
+
+            let mut params = shared_params.clone();
+            if let Some(op_params) = operation["parameters"].as_array() {
+                params.extend(op_params.iter().cloned());
+            }
+
+            let mut req = Request::new(operation_name(operation, method, path));
+            req.folder = title.clone();
+            req.req_type = method.to_uppercase();
+            req.url = operation_url(spec, path, &params);
+            req.headers = operation_headers(spec, &params);
+
+            if is_swagger {
+                set_swagger_body(spec, &mut req, operation, &params);
+            } else if let Some(body) = operation.get("requestBody") {
+                set_openapi_body(spec, &mut req, resolve(spec, body));
+            }
+
+            requests.push(req);
+        }
+    }
+
+    Ok(Collection {
+        requests,
+        environments: server_environments(spec, &title),
+    })
+}
+
+/// Name a request after its operation's id or summary, or its method and
+/// path if it has neither
+fn operation_name(operation: &Value, method: &str, path: &str) -> String {
+    operation["operationId"].as_str()
+        .or_else(|| operation["summary"].as_str())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path))
+}
+
+/// Build an operation's url, with the server's url, path parameters and
+/// required query parameters as variables
+fn operation_url(spec: &Value, path: &str, params: &[Value]) -> String {
+    let path = path.replace('{', "{{").replace('}', "}}");
+    let mut url = format!("{{{{{}}}}}{}", BASE_URL_VAR, path);
+
+    let query: Vec<String> = params.iter()
+        .map(|param| resolve(spec, param))
+        .filter(|param| param["in"] == "query" && param["required"] == true)
+        .filter_map(|param| param["name"].as_str())
+        .map(|name| format!("{}={{{{{}}}}}", name, name))
+        .collect();
+
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
+
+    url
+}
+
+/// Build an operation's headers from its required header parameters, with
+/// their values as variables
+fn operation_headers(spec: &Value, params: &[Value]) -> String {
+    params.iter()
+        .map(|param| resolve(spec, param))
+        .filter(|param| param["in"] == "header" && param["required"] == true)
+        .filter_map(|param| param["name"].as_str())
+        .map(|name| format!("{}: {{{{{}}}}}", name, name))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Set the body of a request from an OpenAPI 3 request body
+fn set_openapi_body(spec: &Value, req: &mut Request, body: &Value) {
+    let content = match body["content"].as_object() {
+        Some(content) if !content.is_empty() => content,
+        _ => return,
+    };
+
+    // Prefer JSON bodies, then forms, then whatever comes first
+    let (content_type, media) = content.iter()
+        .find(|(content_type, _)| content_type.contains("json"))
+        .or_else(|| content.iter().find(|(content_type, _)|
+            content_type.starts_with("application/x-www-form-urlencoded")
+            || content_type.starts_with("multipart/form-data")))
+        .or_else(|| content.iter().next())
+        .unwrap();
+
+    let example = media.get("example").cloned()
+        .or_else(|| media["examples"].as_object()
+            .and_then(|examples| examples.values().next())
+            .map(|example| resolve(spec, example)["value"].clone()))
+        .unwrap_or_else(|| example_for(spec, &media["schema"]));
+
+    if content_type.starts_with("application/x-www-form-urlencoded") {
+        req.body_mode = BodyMode::FormUrlEncoded;
+        req.form = form_fields(&example);
+    } else if content_type.starts_with("multipart/form-data") {
+        req.body_mode = BodyMode::Multipart;
+        req.form = form_fields(&example);
+    } else {
+        req.body_mode = BodyMode::Raw;
+        req.content_type = content_type.clone();
+        req.body = match &example {
+            Value::String(text) => text.clone(),
+            Value::Null => String::new(),
+            _ => serde_json::to_string_pretty(&example).unwrap_or_default(),
+        };
+    }
+}
+
+/// Set the body of a request from Swagger 2 body or form data parameters
+fn set_swagger_body(spec: &Value, req: &mut Request, operation: &Value,
+    params: &[Value]) {
+
+    let consumes = operation["consumes"].as_array()
+        .or_else(|| spec["consumes"].as_array())
+        .and_then(|consumes| consumes.first())
+        .and_then(|content_type| content_type.as_str())
+        .unwrap_or("application/json");
+
+    let params: Vec<&Value> = params.iter()
+        .map(|param| resolve(spec, param))
+        .collect();
+
+    if let Some(body) = params.iter().find(|param| param["in"] == "body") {
+        let example = example_for(spec, &body["schema"]);
+
+        req.body_mode = BodyMode::Raw;
+        req.content_type = consumes.to_string();
+        req.body = serde_json::to_string_pretty(&example).unwrap_or_default();
+        return;
+    }
+
+    let form: Vec<FormField> = params.iter()
+        .filter(|param| param["in"] == "formData")
+        .filter_map(|param| param["name"].as_str().map(|name| FormField {
+            key: name.to_string(),
+            value: if param["type"] == "file" {
+                String::from("@")
+            } else {
+                scalar_text(&example_for(spec, param))
+            },
+        }))
+        .collect();
+
+    if !form.is_empty() {
+        req.body_mode = if consumes.starts_with("multipart/form-data") {
+            BodyMode::Multipart
+        } else {
+            BodyMode::FormUrlEncoded
+        };
+        req.form = form;
+    }
+}
+
+/// Turn the properties of an example object into form fields
+fn form_fields(example: &Value) -> Vec<FormField> {
+    example.as_object()
+        .map(|object| object.iter()
+            .map(|(key, value)| FormField {
+                key: key.clone(),
+                value: scalar_text(value),
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Text of a JSON value without quotes around strings
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+/// Generate an example value matching a schema
+///
+/// Uses the schema's own example, default or first enum value if it has one
+pub fn example_for(spec: &Value, schema: &Value) -> Value {
+    example_with_refs(spec, schema, &mut Vec::new())
+}
+
+/// Generate an example value matching a schema, skipping schemas that refer
+/// back to one of the refs already being expanded
+fn example_with_refs<'a>(spec: &'a Value, schema: &'a Value,
+    refs: &mut Vec<&'a str>) -> Value {
+
+    let reference = schema.get("$ref").and_then(|r| r.as_str());
+    if let Some(reference) = reference {
+        if refs.contains(&reference) || refs.len() > MAX_REF_DEPTH {
+            return Value::Null;
+        }
+        refs.push(reference);
+    }
+
+    let example = example_of(spec, resolve(spec, schema), refs);
+
+    if reference.is_some() {
+        refs.pop();
+    }
+
+    example
+}
+
+/// Generate an example value matching a schema that isn't a `$ref`
+fn example_of<'a>(spec: &'a Value, schema: &'a Value,
+    refs: &mut Vec<&'a str>) -> Value {
+
+    if schema.is_null() {
+        return Value::Null;
+    }
+
+    if let Some(example) = schema.get("example")
+        .or_else(|| schema.get("default"))
+        .or_else(|| schema["enum"].as_array().and_then(|e| e.first())) {
+
+        return example.clone();
+    }
+
+    if let Some(all_of) = schema["allOf"].as_array() {
+        let mut merged = Map::new();
+        for part in all_of {
+            if let Value::Object(object) = example_with_refs(spec, part, refs) {
+                merged.extend(object);
+            }
+        }
+        return Value::Object(merged);
+    }
+
+    if let Some(first) = schema["oneOf"].as_array()
+        .or_else(|| schema["anyOf"].as_array())
+        .and_then(|schemas| schemas.first()) {
+
+        return example_with_refs(spec, first, refs);
+    }
+
+    let schema_type = schema["type"].as_str()
+        .or_else(|| schema["type"].as_array()
+            .and_then(|types| types.iter().find_map(|t| t.as_str()
+                .filter(|t| *t != "null"))))
+        .unwrap_or(if schema.get("properties").is_some() {
+            "object"
+        } else {
+            ""
+        });
+
+    match schema_type {
+        // Properties that only refer back to a schema being expanded are left
+        // out
+        "object" => Value::Object(schema["properties"].as_object()
+            .map(|properties| properties.iter()
+                .map(|(name, property)| (name.clone(),
+                    example_with_refs(spec, property, refs)))
+                .filter(|(_, example)| !example.is_null())
+                .collect())
+            .unwrap_or_default()),
+        "array" => match example_with_refs(spec, &schema["items"], refs) {
+            Value::Null => json!([]),
+            item => json!([item]),
+        },
+        "integer" => json!(0),
+        "number" => json!(0.0),
+        "boolean" => json!(true),
+        "string" => json!(match schema["format"].as_str() {
+            Some("date") => "2000-01-01",
+            Some("date-time") => "2000-01-01T00:00:00Z",
+            Some("email") => "user@example.com",
+            Some("uuid") => "00000000-0000-0000-0000-000000000000",
+            Some("uri") | Some("url") => "https://example.com",
+            _ => "string",
+        }),
+        _ => Value::Null,
+    }
+}
+
+/// Create an environment for each server in the spec, with its url in the
+/// `baseUrl` variable
+fn server_environments(spec: &Value, title: &str) -> Vec<Environment> {
+    // Swagger 2 specs have a single server split into parts
+    if let Some(host) = spec["host"].as_str() {
+        let scheme = spec["schemes"].as_array()
+            .and_then(|schemes| schemes.first())
+            .and_then(|scheme| scheme.as_str())
+            .unwrap_or("https");
+        let base_path = spec["basePath"].as_str().unwrap_or("");
+
+        let mut env = Environment::new(title);
+        env.variables.insert(BASE_URL_VAR.to_string(),
+            format!("{}://{}{}", scheme, host, base_path.trim_end_matches('/')));
+        return vec![env];
+    }
+
+    spec["servers"].as_array()
+        .map(|servers| servers.iter()
+            .filter_map(|server| {
+                let mut url = server["url"].as_str()?.to_string();
+
+                // Fill in server variables with their defaults
+                if let Some(variables) = server["variables"].as_object() {
+                    for (name, variable) in variables {
+                        if let Some(default) = variable["default"].as_str() {
+                            url = url.replace(&format!("{{{}}}", name), default);
+                        }
+                    }
+                }
+
+                let name = match server["description"].as_str() {
+                    Some(description) => format!("{} - {}", title, description),
+                    None => format!("{} - {}", title, url),
+                };
+
+                let mut env = Environment::new(name);
+                env.variables.insert(BASE_URL_VAR.to_string(),
+                    url.trim_end_matches('/').to_string());
+                Some(env)
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Find the operation in a spec matching a request's method and url path
+///
+/// The path template only has to match the end of the url path, since the
+/// server's url may add segments to the start. Returns the operation's path
+/// template and the operation
+pub fn find_operation<'a>(spec: &'a Value, method: &str, url_path: &str)
+    -> Option<(&'a str, &'a Value)> {
+
+    let method = method.to_lowercase();
+
+    spec["paths"].as_object()?
+        .iter()
+        .filter(|(template, _)| path_matches(template, url_path))
+        .filter_map(|(template, item)| resolve(spec, item).get(&method)
+            .map(|operation| (template.as_str(), operation)))
+        // Prefer the templates with the most literal segments, so
+        // `/pets/mine` wins over `/pets/{id}`, then the longest ones, so
+        // `/store/pets` wins over `/pets`
+        .max_by_key(|(template, _)| {
+            let segments = template_segments(template);
+            let literals = segments.iter()
+                .filter(|segment| !is_parameter(segment))
+                .count();
+            (literals, segments.len())
+        })
+}
+
+/// Does the end of a url path match a path template?
+fn path_matches(template: &str, url_path: &str) -> bool {
+    let template = template_segments(template);
+    let path = template_segments(url_path);
+
+    if path.len() < template.len() {
+        return false;
+    }
+
+    template.iter()
+        .zip(path[path.len() - template.len()..].iter())
+        .all(|(expected, actual)| is_parameter(expected) || expected == actual)
+}
+
+/// The non-empty segments of a path or path template
+fn template_segments(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+/// Is a path template segment a parameter, like `{id}`?
+fn is_parameter(segment: &str) -> bool {
+    segment.starts_with('{') && segment.ends_with('}')
+}
+
+/// Validate a response's status, headers and body against the responses an
+/// operation declares
+///
+/// The body is `None` when it was too big to keep in memory, and then it isn't
+/// checked against its schema
+pub fn validate_response(spec: &Value, operation: &Value, status: &str,
+    headers: &[(String, String)], body: Option<&[u8]>) -> Vec<Violation> {
+
+    let mut violations = Vec::new();
+    let responses = &operation["responses"];
+
+    // Look for the exact status, then its range, like 2XX, then the default
+    let range = format!("{}XX", status.chars().next().unwrap_or('0'));
+    let response = match [status, range.as_str(), "default"].iter()
+        .find_map(|key| responses.get(*key)) {
+
+        Some(response) => resolve(spec, response),
+        None => {
+            violations.push(Violation {
+                pointer: String::from("status"),
+                message: format!("{} isn't a declared response status", status),
+            });
+            return violations;
+        },
+    };
+
+    let header_value = |name: &str| headers.iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str());
+
+    if let Some(declared) = response["headers"].as_object() {
+        for (name, header) in declared {
+            let header = resolve(spec, header);
+            let pointer = format!("header {}", name);
+
+            match header_value(name) {
+                Some(value) => {
+                    // Header values are parsed as JSON for schemas of numbers
+                    // and booleans
+                    let schema = header.get("schema").unwrap_or(header);
+                    let value = serde_json::from_str(value)
+                        .ok()
+                        .filter(|_| schema["type"] != "string")
+                        .unwrap_or_else(|| Value::String(value.to_string()));
+
+                    for violation in schema::validate(spec, schema, &value) {
+                        violations.push(Violation {
+                            pointer: pointer.clone(),
+                            message: violation.message,
+                        });
+                    }
+                },
+                None if header["required"] == true => violations.push(
+                    Violation {
+                        pointer,
+                        message: String::from("Missing required header"),
+                    }),
+                None => (),
+            }
+        }
+    }
+
+    // OpenAPI 3 declares a schema per content type, Swagger 2 has just one
+    let content_type = header_value("content-type").unwrap_or("");
+    let base_type = content_type.split(';').next().unwrap_or("").trim();
+    let body_schema = match response["content"].as_object() {
+        Some(content) => {
+            let media = content.get(base_type)
+                .or_else(|| base_type.split_once('/').and_then(|(kind, _)|
+                    content.get(&format!("{}/*", kind))))
+                .or_else(|| content.get("*/*"));
+
+            let is_empty = body.is_some_and(|body| body.is_empty());
+            if media.is_none() && !base_type.is_empty() && !is_empty {
+                violations.push(Violation {
+                    pointer: String::from("header content-type"),
+                    message: format!("{} isn't a declared content type",
+                        base_type),
+                });
+            }
+
+            media.and_then(|media| media.get("schema"))
+        },
+        None => response.get("schema"),
+    };
+
+    // Only JSON bodies can be checked against a schema
+    if let Some(body_schema) = body_schema {
+        if base_type.contains("json") {
+            match body.map(serde_json::from_slice::<Value>) {
+                Some(Ok(body)) => violations.extend(
+                    schema::validate(spec, body_schema, &body)),
+                Some(Err(e)) => violations.push(Violation {
+                    pointer: String::from("body"),
+                    message: format!("Isn't valid JSON: {}", e),
+                }),
+                None => violations.push(body_too_big()),
+            }
+        }
+    }
+
+    violations
+}
+
+/// The violation for a body too big to check
+pub fn body_too_big() -> Violation {
+    Violation {
+        pointer: String::from("body"),
+        message: format!("Is more than {}, so it wasn't checked",
+            format_size(PREVIEW_LEN as u64)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        example_for,
+        find_operation,
+        to_collection,
+        validate_response,
+    };
+    use crate::app::request::BodyMode;
+
+    /// The template of the operation found for a method and url path
+    fn template(spec: &serde_json::Value, method: &str, url_path: &str)
+        -> Option<String> {
+
+        find_operation(spec, method, url_path)
+            .map(|(template, _)| template.to_string())
+    }
+
+    #[test]
+    fn root_template_only_matches_as_a_last_resort() {
+        let spec = json!({ "paths": {
+            "/": { "get": {} },
+            "/pets": { "get": {} },
+        } });
+
+        assert_eq!(template(&spec, "GET", "/pets").as_deref(), Some("/pets"));
+        assert_eq!(template(&spec, "GET", "/").as_deref(), Some("/"));
+    }
+
+    #[test]
+    fn longer_templates_win() {
+        let spec = json!({ "paths": {
+            "/pets": { "get": {} },
+            "/store/pets": { "get": {} },
+        } });
+
+        assert_eq!(template(&spec, "GET", "/api/store/pets").as_deref(),
+            Some("/store/pets"));
+        assert_eq!(template(&spec, "GET", "/api/pets").as_deref(),
+            Some("/pets"));
+    }
+
+    #[test]
+    fn literal_segments_win_over_parameters() {
+        let spec = json!({ "paths": {
+            "/pets/{id}": { "get": {} },
+            "/pets/mine": { "get": {} },
+        } });
+
+        assert_eq!(template(&spec, "GET", "/pets/mine").as_deref(),
+            Some("/pets/mine"));
+        assert_eq!(template(&spec, "GET", "/pets/7").as_deref(),
+            Some("/pets/{id}"));
+    }
+
+    #[test]
+    fn templates_without_the_method_are_skipped() {
+        let spec = json!({ "paths": {
+            "/pets/mine": { "get": {} },
+            "/pets/{id}": { "delete": {} },
+        } });
+
+        assert_eq!(template(&spec, "DELETE", "/pets/mine").as_deref(),
+            Some("/pets/{id}"));
+        assert_eq!(template(&spec, "PUT", "/pets/mine"), None);
+    }
+
+    #[test]
+    fn path_items_can_be_references() {
+        let spec = json!({
+            "paths": { "/pets": { "$ref": "#/components/pathItems/pets" } },
+            "components": { "pathItems": { "pets": { "post": {} } } },
+        });
+
+        assert_eq!(template(&spec, "post", "/pets").as_deref(), Some("/pets"));
+    }
+
+    #[test]
+    fn skips_bodies_too_big_to_check() {
+        let spec = json!({});
+        let operation = json!({ "responses": { "200": { "content": {
+            "application/json": { "schema": { "type": "object" } },
+        } } } });
+        let headers = [(String::from("Content-Type"),
+            String::from("application/json"))];
+
+        let check = |body| validate_response(&spec, &operation, "200",
+            &headers, body);
+
+        assert!(check(Some(b"{}")).is_empty());
+        assert_eq!(check(Some(b"[]")).len(), 1);
+        let violations = check(None);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].pointer, "body");
+    }
+
+    #[test]
+    fn imports_parameters_as_variables() {
+        let spec = json!({
+            "openapi": "3.0.0",
+            "info": { "title": "Pets" },
+            "paths": { "/pets/{id}": {
+                "parameters": [{ "$ref": "#/components/parameters/id" }],
+                "get": {
+                    "operationId": "getPet",
+                    "parameters": [
+                        { "name": "fields", "in": "query", "required": true },
+                        { "name": "page", "in": "query" },
+                        { "name": "X-Key", "in": "header", "required": true },
+                    ],
+                },
+                "delete": {},
+            } },
+            "components": { "parameters": {
+                "id": { "name": "id", "in": "path", "required": true },
+            } },
+        });
+
+        let collection = to_collection(&spec).unwrap();
+        let requests = &collection.requests;
+        assert_eq!(requests.len(), 2);
+
+        assert_eq!(requests[0].name, "getPet");
+        assert_eq!(requests[0].folder, "Pets");
+        assert_eq!(requests[0].req_type, "GET");
+        assert_eq!(requests[0].url,
+            "{{baseUrl}}/pets/{{id}}?fields={{fields}}");
+        assert_eq!(requests[0].headers, "X-Key: {{X-Key}}");
+
+        assert_eq!(requests[1].name, "DELETE /pets/{id}");
+        assert_eq!(requests[1].url, "{{baseUrl}}/pets/{{id}}");
+    }
+
+    #[test]
+    fn builds_example_bodies_from_schemas() {
+        let spec = json!({
+            "openapi": "3.0.0",
+            "paths": { "/pets": { "post": { "requestBody": { "content": {
+                "text/plain": {},
+                "application/json": {
+                    "schema": { "$ref": "#/components/schemas/Pet" },
+                },
+            } } } } },
+            "components": { "schemas": { "Pet": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "example": "Rex" },
+                    "born": { "type": "string", "format": "date" },
+                    "kind": { "enum": ["dog", "cat"] },
+                    "tags": { "type": "array", "items": { "type": "integer" } },
+                    "parent": { "$ref": "#/components/schemas/Pet" },
+                },
+            } } },
+        });
+
+        let pet = json!({
+            "name": "Rex",
+            "born": "2000-01-01",
+            "kind": "dog",
+            "tags": [0],
+        });
+        assert_eq!(example_for(&spec, &json!({
+            "$ref": "#/components/schemas/Pet",
+        })), pet);
+
+        let collection = to_collection(&spec).unwrap();
+        let req = &collection.requests[0];
+        assert!(req.body_mode == BodyMode::Raw);
+        assert_eq!(req.content_type, "application/json");
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&req.body)
+            .unwrap(), pet);
+    }
+
+    #[test]
+    fn imports_servers_as_environments() {
+        let spec = json!({
+            "openapi": "3.0.0",
+            "info": { "title": "Pets" },
+            "paths": {},
+            "servers": [
+                {
+                    "url": "https://{region}.example.com/v1/",
+                    "description": "Production",
+                    "variables": { "region": { "default": "eu" } },
+                },
+                { "url": "http://localhost:8080" },
+            ],
+        });
+
+        let environments = to_collection(&spec).unwrap().environments;
+        let servers: Vec<(&str, &str)> = environments.iter()
+            .map(|env| (env.name.as_str(), env.variables["baseUrl"].as_str()))
+            .collect();
+
+        assert_eq!(servers, [
+            ("Pets - Production", "https://eu.example.com/v1"),
+            ("Pets - http://localhost:8080", "http://localhost:8080"),
+        ]);
+    }
+
+    #[test]
+    fn imports_swagger_specs() {
+        let spec = json!({
+            "swagger": "2.0",
+            "info": { "title": "Store" },
+            "host": "store.example.com",
+            "basePath": "/api/",
+            "schemes": ["http"],
+            "paths": {
+                "/orders": { "post": { "parameters": [{
+                    "name": "order",
+                    "in": "body",
+                    "schema": { "$ref": "#/definitions/Order" },
+                }] } },
+                "/uploads": { "post": {
+                    "consumes": ["multipart/form-data"],
+                    "parameters": [
+                        { "name": "file", "in": "formData", "type": "file" },
+                        { "name": "size", "in": "formData", "type": "integer" },
+                    ],
+                } },
+            },
+            "definitions": { "Order": {
+                "properties": { "count": { "type": "integer" } },
+            } },
+        });
+
+        let collection = to_collection(&spec).unwrap();
+        let order = &collection.requests[0];
+        assert_eq!(order.url, "{{baseUrl}}/orders");
+        assert_eq!(order.content_type, "application/json");
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&order.body)
+            .unwrap(), json!({ "count": 0 }));
+
+        let upload = &collection.requests[1];
+        assert!(upload.body_mode == BodyMode::Multipart);
+        let form: Vec<(&str, &str)> = upload.form.iter()
+            .map(|field| (field.key.as_str(), field.value.as_str()))
+            .collect();
+        assert_eq!(form, [("file", "@"), ("size", "0")]);
+
+        assert_eq!(collection.environments.len(), 1);
+        assert_eq!(collection.environments[0].name, "Store");
+        assert_eq!(collection.environments[0].variables["baseUrl"],
+            "http://store.example.com/api");
+    }
+}
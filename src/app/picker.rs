@@ -0,0 +1,94 @@
+//! # picker
+//!
+//! `picker` implements a popup list to choose an item from, like the template
+//! a new request starts from.
+
+use crossterm::event::{
+    KeyCode,
+    KeyModifiers,
+};
+
+use super::{
+    config::Action,
+    App,
+};
+
+/// What choosing an item in a picker does
+#[derive(Clone, Copy, PartialEq)]
+pub enum PickerAction {
+    // Create a request from the template at the index, after the blank one
+    Template,
+    // Set the method of the selected request, or type a custom one after
+    // the last item
+    Method,
+}
+
+/// A popup list of items to choose from
+pub struct Picker {
+    pub title: String,
+    pub items: Vec<String>,
+    // Index of the highlighted item
+    pub index: usize,
+    pub action: PickerAction,
+}
+
+impl Picker {
+    /// Create a picker with the first item highlighted
+    pub fn new<T: ToString>(title: T, items: Vec<String>,
+        action: PickerAction) -> Self {
+
+        Self {
+            title: title.to_string(),
+            items,
+            index: 0,
+            action,
+        }
+    }
+}
+
+impl App {
+    /// Handle key events while a picker is open
+    ///
+    /// Uses the normal mode keys, and the arrow keys move too
+    pub fn picker_keys(&mut self, modifiers: KeyModifiers, code: KeyCode) {
+        let picker = match self.picker.as_mut() {
+            Some(picker) => picker,
+            None => return,
+        };
+        let len = picker.items.len().max(1);
+        let action = self.config.keys.action(false, modifiers, code);
+
+        match (action, code) {
+            (Some(Action::Quit), _) => self.picker = None,
+            (Some(Action::Down), _) | (_, KeyCode::Down) =>
+                picker.index = (picker.index + 1) % len,
+            (Some(Action::Up), _) | (_, KeyCode::Up) =>
+                picker.index = (picker.index + len - 1) % len,
+            (Some(Action::Run), _) => {
+                let (action, index) = (picker.action, picker.index);
+                self.picker = None;
+                self.pick(action, index);
+            },
+            // Delete the highlighted template
+            (Some(Action::Delete), _) if picker.action == PickerAction::Template
+                && picker.index > 0 => {
+
+                let index = picker.index - 1;
+                self.delete_template(index);
+                self.open_template_picker();
+                if let Some(picker) = self.picker.as_mut() {
+                    picker.index = index.min(picker.items.len() - 1);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Do the action of a picker with the item chosen
+    fn pick(&mut self, action: PickerAction, index: usize) {
+        match action {
+            PickerAction::Template => self.create_request(index.checked_sub(1)),
+            PickerAction::Method => self.choose_method(index),
+        }
+    }
+}
@@ -3,8 +3,28 @@
 //! The `request` crate contains structures and methods for running the HTTP
 //! requests.
 
+use std::{
+    fs::File,
+    io,
+    path::PathBuf,
+    sync::mpsc::{
+        self,
+        RecvTimeoutError,
+    },
+    thread,
+    time::{
+        Duration,
+        Instant,
+        SystemTime,
+    },
+};
+
 use reqwest::{
-    blocking,
+    blocking::{
+        self,
+        multipart,
+    },
+    header::CONTENT_TYPE,
     Method,
 };
 use serde::{
@@ -12,22 +32,325 @@ use serde::{
     Serialize,
 };
 
-/// Number of request fields that can be edited in the UI
-pub const REQ_FIELD_COUNT: usize = 4;
+use super::{
+    config::Config,
+    download::Download,
+    environment::{
+        self,
+        Variables,
+    },
+    graphql::{
+        self,
+        GraphQlBody,
+    },
+    sse::{
+        EVENT_STREAM_TYPE,
+        SseStream,
+    },
+    validation::CheckResult,
+    websocket::{
+        WEBSOCKET_TYPE,
+        WsSession,
+    },
+};
+
+/// Methods that can be chosen for a request, besides WebSocket and custom
+/// ones
+pub const METHODS: [&str; 9] = [
+    "GET",
+    "POST",
+    "PUT",
+    "PATCH",
+    "DELETE",
+    "HEAD",
+    "OPTIONS",
+    "TRACE",
+    "CONNECT",
+];
+
+/// Content types that can be cycled through for raw bodies
+pub const RAW_CONTENT_TYPES: [&str; 5] = [
+    "text/plain",
+    "application/json",
+    "application/xml",
+    "text/html",
+    "application/javascript",
+];
+
+/// How the body of a request is encoded when it is sent
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum BodyMode {
+    #[default]
+    Raw,
+    FormUrlEncoded,
+    Multipart,
+    Binary,
+    GraphQl,
+}
+
+impl BodyMode {
+    /// Every body mode, in the order they are cycled through in the UI
+    pub const ALL: [BodyMode; 5] = [
+        BodyMode::Raw,
+        BodyMode::FormUrlEncoded,
+        BodyMode::Multipart,
+        BodyMode::Binary,
+        BodyMode::GraphQl,
+    ];
+
+    /// Name of the body mode shown and typed in the UI
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BodyMode::Raw => "raw",
+            BodyMode::FormUrlEncoded => "form-urlencoded",
+            BodyMode::Multipart => "multipart",
+            BodyMode::Binary => "binary",
+            BodyMode::GraphQl => "graphql",
+        }
+    }
+
+    /// Get the body mode matching a name typed in the UI
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter()
+            .find(|mode| mode.as_str().eq_ignore_ascii_case(name.trim()))
+            .copied()
+    }
+
+    /// Get the body mode after this one, wrapping around to the first
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|mode| mode == self)
+            .unwrap_or(0);
+
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Title of the body editor in the info panel for this mode
+    pub fn editor_title(&self) -> &'static str {
+        match self {
+            BodyMode::Raw => "Body",
+            BodyMode::FormUrlEncoded => "Form Fields (key=value&key=value)",
+            BodyMode::Multipart => "Multipart Parts (key=value&file=@path)",
+            BodyMode::Binary => "Body File Path",
+            BodyMode::GraphQl => "GraphQL Query",
+        }
+    }
+}
+
+/// Request attributes that can be edited in the info panel
+#[derive(Clone, Copy, PartialEq)]
+pub enum ReqField {
+    Name,
+    Method,
+    Url,
+    Headers,
+    BodyMode,
+    ContentType,
+    Body,
+    Variables,
+    OperationName,
+    // JSON Schema the response body is checked against
+    ResponseSchema,
+    // Text frame to send over a WebSocket
+    Message,
+}
+
+impl ReqField {
+    /// Get the fields shown in the info panel for a body mode, in order
+    pub fn for_mode(body_mode: BodyMode) -> Vec<Self> {
+        let mut fields = vec![
+            ReqField::Name,
+            ReqField::Method,
+            ReqField::Url,
+            ReqField::Headers,
+            ReqField::BodyMode,
+        ];
+
+        match body_mode {
+            BodyMode::Raw => fields.extend([
+                ReqField::ContentType,
+                ReqField::Body,
+            ]),
+            BodyMode::GraphQl => fields.extend([
+                ReqField::Body,
+                ReqField::Variables,
+                ReqField::OperationName,
+            ]),
+            _ => fields.push(ReqField::Body),
+        };
+        fields.push(ReqField::ResponseSchema);
+
+        fields
+    }
+
+    /// Get the fields shown in the info panel for a WebSocket request
+    pub fn for_websocket() -> Vec<Self> {
+        vec![
+            ReqField::Name,
+            ReqField::Method,
+            ReqField::Url,
+            ReqField::Headers,
+            ReqField::Message,
+        ]
+    }
+
+    /// Title of the field's box in the info panel
+    pub fn title(&self, body_mode: BodyMode) -> &'static str {
+        match self {
+            ReqField::Name => "Name",
+            ReqField::Method => "Request Type",
+            ReqField::Url => "Url",
+            ReqField::Headers => "Headers (Name: value per line)",
+            ReqField::BodyMode => "Body Mode",
+            ReqField::ContentType => "Content Type",
+            ReqField::Body => body_mode.editor_title(),
+            ReqField::Variables => "GraphQL Variables",
+            ReqField::OperationName => "Operation Name",
+            ReqField::ResponseSchema => "Response Schema (JSON or file path)",
+            ReqField::Message => "Message (Enter to send)",
+        }
+    }
+
+    /// Can the field's text span multiple lines?
+    pub fn is_multiline(&self) -> bool {
+        matches!(self, ReqField::Headers | ReqField::Body | ReqField::Variables
+            | ReqField::Message)
+    }
+}
+
+/// A key/value pair sent in a form-urlencoded or multipart body
+///
+/// In multipart bodies, a value starting with `@` is read as a path to a file
+/// to upload, the same as curl's `-F` option
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FormField {
+    pub key: String,
+    pub value: String,
+}
+
+impl FormField {
+    /// Parse fields from the `key=value&key=value` text used in the UI
+    pub fn parse_list(text: &str) -> Vec<Self> {
+        text.split('&')
+            .filter(|pair| !pair.trim().is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => Self {
+                    key: key.trim().to_string(),
+                    value: value.to_string(),
+                },
+                None => Self {
+                    key: pair.trim().to_string(),
+                    value: String::new(),
+                },
+            })
+            .collect()
+    }
+
+    /// Format fields as the `key=value&key=value` text used in the UI
+    pub fn format_list(fields: &[Self]) -> String {
+        fields.iter()
+            .map(|field| format!("{}={}", field.key, field.value))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
 
 /// Contains the data for an HTTP request
 #[derive(Serialize, Deserialize)]
 pub struct Request {
     // The name of the request seen in the UI
     pub name: String,
+    // Folder the request is grouped under in the request list, if any
+    #[serde(default)]
+    pub folder: String,
     pub req_type: String, // String for serialization, gets converted to Method
     pub url: String,
+    // Headers sent with the request, one `Name: value` per line
+    #[serde(default)]
+    pub headers: String,
+    #[serde(default)]
+    pub body_mode: BodyMode,
+    // Content type sent with raw bodies
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
     pub body: String,
+    // Fields for form-urlencoded and multipart bodies
+    #[serde(default)]
+    pub form: Vec<FormField>,
+    // Path of the file sent as a binary body
+    #[serde(default)]
+    pub body_file: String,
+    #[serde(default)]
+    pub graphql: GraphQlBody,
+    // Type and field names loaded by introspecting a GraphQL schema
+    #[serde(skip)]
+    pub gql_schema: Vec<String>,
+    // Introspection query running on its own thread, if any
+    #[serde(skip)]
+    gql_loading: Option<mpsc::Receiver<Result<Vec<String>, String>>>,
+    // Text frame being composed for a WebSocket request
+    #[serde(default)]
+    pub ws_message: String,
+    #[serde(skip)]
+    pub ws_session: Option<WsSession>,
+    // Events streamed from a `text/event-stream` response
+    #[serde(skip)]
+    pub sse_stream: Option<SseStream>,
+    // Body of the last response, which is streamed to a file while the
+    // start of it is shown from `preview`
+    #[serde(skip)]
+    pub download: Option<Download>,
+    // Text shown for the start of a downloaded body, kept out of `resp` so
+    // it isn't saved with the request
+    #[serde(skip)]
+    pub preview: String,
+    // Extra information shown next to the status, like download progress
+    #[serde(skip)]
+    pub notice: String,
+    // Headers and HTTP version of the last response
+    #[serde(skip)]
+    pub resp_headers: Vec<(String, String)>,
+    #[serde(skip)]
+    pub resp_version: String,
+    // When the last request was sent, and how long the server took to
+    // start responding
+    #[serde(skip)]
+    pub sent_at: Option<SystemTime>,
+    #[serde(skip)]
+    pub wait_time: Duration,
+    // Whether the request is marked to be exported
+    #[serde(skip)]
+    pub marked: bool,
+    // Index of the `.http` file the request was opened from, and the id of
+    // its block in the file
+    #[serde(skip)]
+    pub http_block: Option<(usize, usize)>,
+    // Path of the OpenAPI spec the request's responses are validated against
+    #[serde(default)]
+    pub spec_path: String,
+    // JSON Schema response bodies are checked against, written inline or as
+    // a path to a schema file
+    #[serde(default)]
+    pub response_schema: String,
+    // Whether the response still has to be checked once it has downloaded,
+    // and the results of checking it
+    #[serde(skip)]
+    pub checks_pending: bool,
+    #[serde(skip)]
+    pub checks: Vec<CheckResult>,
+    // The last response, and the id used to access the request in its store,
+    // which are left out of request files
+    #[serde(default)]
     pub resp: String,
+    #[serde(default)]
     pub status: String,
+    #[serde(default)]
     pub db_id: String, // Id used to access request in JSON database
 }
 
+fn default_content_type() -> String {
+    RAW_CONTENT_TYPES[0].to_string()
+}
+
 impl Request {
     /// Create a new blank request
     ///
@@ -35,36 +358,523 @@ impl Request {
     pub fn new<T: ToString>(name: T) -> Self {
         Self {
             name: name.to_string(),
+            folder: String::new(),
             req_type: Method::GET.to_string(),
             url: String::new(),
+            headers: String::new(),
+            body_mode: BodyMode::Raw,
+            content_type: default_content_type(),
             body: String::new(),
+            form: Vec::new(),
+            body_file: String::new(),
+            graphql: GraphQlBody::default(),
+            gql_schema: Vec::new(),
+            gql_loading: None,
+            ws_message: String::new(),
+            ws_session: None,
+            sse_stream: None,
+            download: None,
+            preview: String::new(),
+            notice: String::new(),
+            resp_headers: Vec::new(),
+            resp_version: String::new(),
+            sent_at: None,
+            wait_time: Duration::ZERO,
+            marked: false,
+            http_block: None,
+            spec_path: String::new(),
+            response_schema: String::new(),
+            checks_pending: false,
+            checks: Vec::new(),
             resp: String::new(),
             status: String::new(),
             db_id: String::new(),
         }
     }
 
+    /// Is this a WebSocket request instead of an HTTP one?
+    pub fn is_websocket(&self) -> bool {
+        self.req_type == WEBSOCKET_TYPE
+    }
+
+    /// Get the fields shown in the info panel for this request
+    pub fn fields(&self) -> Vec<ReqField> {
+        if self.is_websocket() {
+            ReqField::for_websocket()
+        } else {
+            ReqField::for_mode(self.body_mode)
+        }
+    }
+
+    /// Parse the headers text into name/value pairs, skipping blank lines
+    pub fn header_pairs(&self) -> Vec<(String, String)> {
+        self.headers.lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(),
+                value.trim().to_string()))
+            .filter(|(name, _)| !name.is_empty())
+            .collect()
+    }
+
+    /// Whether the headers set the Content-Type of the body themselves
+    fn has_content_type(&self) -> bool {
+        self.header_pairs().iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+    }
+
+    /// Text shown in the info panel for a field
+    pub fn field_text(&self, field: ReqField) -> String {
+        match field {
+            ReqField::Name => self.name.clone(),
+            ReqField::Method => self.req_type.clone(),
+            ReqField::Url => self.url.clone(),
+            ReqField::Headers => self.headers.clone(),
+            ReqField::BodyMode => self.body_mode.as_str().to_string(),
+            ReqField::ContentType => self.content_type.clone(),
+            ReqField::Body => match self.body_mode {
+                BodyMode::Raw => self.body.clone(),
+                BodyMode::FormUrlEncoded | BodyMode::Multipart =>
+                    FormField::format_list(&self.form),
+                BodyMode::Binary => self.body_file.clone(),
+                BodyMode::GraphQl => self.graphql.query.clone(),
+            },
+            ReqField::Variables => self.graphql.variables.clone(),
+            ReqField::OperationName => self.graphql.operation_name.clone(),
+            ReqField::ResponseSchema => self.response_schema.clone(),
+            ReqField::Message => self.ws_message.clone(),
+        }
+    }
+
+    /// Update a field with text from the info panel
+    ///
+    /// Text that isn't valid for the field, like an unknown method, is ignored
+    pub fn set_field_text(&mut self, field: ReqField, text: &str) {
+        match field {
+            ReqField::Name => self.name = text.to_string(),
+            ReqField::Method => if text.trim()
+                .eq_ignore_ascii_case(WEBSOCKET_TYPE) {
+
+                self.req_type = WEBSOCKET_TYPE.to_string();
+            } else if let Ok(method) = Method::from_bytes(text.as_bytes()) {
+                self.req_type = method.to_string();
+            },
+            ReqField::Url => self.url = text.to_string(),
+            ReqField::Headers => self.headers = text.to_string(),
+            ReqField::BodyMode => if let Some(body_mode)
+                = BodyMode::from_name(text) {
+
+                self.body_mode = body_mode;
+            },
+            ReqField::ContentType => self.content_type = text.trim().to_string(),
+            ReqField::Body => match self.body_mode {
+                BodyMode::Raw => self.body = text.to_string(),
+                BodyMode::FormUrlEncoded | BodyMode::Multipart =>
+                    self.form = FormField::parse_list(text),
+                BodyMode::Binary => self.body_file = text.trim().to_string(),
+                BodyMode::GraphQl => self.graphql.query = text.to_string(),
+            },
+            ReqField::Variables => self.graphql.variables = text.to_string(),
+            ReqField::OperationName => self.graphql.operation_name
+                = text.trim().to_string(),
+            ReqField::ResponseSchema => self.response_schema
+                = text.trim().to_string(),
+            ReqField::Message => self.ws_message = text.to_string(),
+        }
+    }
+
+    /// Copy the saved fields of the request into a new request, which hasn't
+    /// been saved yet and has no response
+    pub fn duplicate(&self) -> Self {
+        let mut copy: Self = serde_json::to_value(self)
+            .and_then(serde_json::from_value)
+            .unwrap_or_else(|_| Self::new(&self.name));
+        copy.resp = String::new();
+        copy.status = String::new();
+        copy.db_id = String::new();
+
+        copy
+    }
+
+    /// Replace the saved fields of the request with another request's,
+    /// keeping its response, connections and id
+    pub fn set_saved_fields(&mut self, other: Self) {
+        self.name = other.name;
+        self.folder = other.folder;
+        self.req_type = other.req_type;
+        self.url = other.url;
+        self.headers = other.headers;
+        self.body_mode = other.body_mode;
+        self.content_type = other.content_type;
+        self.body = other.body;
+        self.form = other.form;
+        self.body_file = other.body_file;
+        self.graphql = other.graphql;
+        self.ws_message = other.ws_message;
+        self.spec_path = other.spec_path;
+        self.response_schema = other.response_schema;
+    }
+
+    /// Label of the request in the request list
+    pub fn label(&self) -> String {
+        let mark = if self.marked { "* " } else { "" };
+
+        if self.folder.is_empty() {
+            format!("{}{}", mark, self.name)
+        } else {
+            format!("{}{} / {}", mark, self.folder, self.name)
+        }
+    }
+
+    /// Copy the parts of the request that are sent, with `{{name}}`
+    /// placeholders replaced by the values of variables
+    pub fn resolve(&self, variables: &Variables) -> Self {
+        let sub = |text: &str| environment::substitute(text, variables);
+
+        let mut resolved = Self::new(&self.name);
+        resolved.req_type = self.req_type.clone();
+        resolved.url = sub(&self.url);
+        resolved.headers = sub(&self.headers);
+        resolved.body_mode = self.body_mode;
+        resolved.content_type = self.content_type.clone();
+        resolved.body = sub(&self.body);
+        resolved.form = self.form.iter()
+            .map(|field| FormField {
+                key: sub(&field.key),
+                value: sub(&field.value),
+            })
+            .collect();
+        resolved.body_file = sub(&self.body_file);
+        resolved.graphql = GraphQlBody {
+            query: self.graphql.query.clone(),
+            variables: sub(&self.graphql.variables),
+            operation_name: self.graphql.operation_name.clone(),
+        };
+        resolved.ws_message = sub(&self.ws_message);
+
+        resolved
+    }
+
+    /// Get the url with `{{name}}` placeholders replaced by the values of
+    /// variables
+    pub fn resolved_url(&self, variables: &Variables) -> String {
+        environment::substitute(&self.url, variables)
+    }
+
+    /// Open a WebSocket session if one isn't open, otherwise send the
+    /// composed message over it
+    ///
+    /// Takes in the variables of the active environment
+    pub fn run_websocket(&mut self, variables: &Variables) {
+        let resolved = self.resolve(variables);
+
+        match &self.ws_session {
+            Some(session) if session.is_open => {
+                if !resolved.ws_message.is_empty() {
+                    session.send(resolved.ws_message);
+                }
+            },
+            _ => {
+                self.ws_session = Some(WsSession::connect(&resolved.url,
+                    resolved.header_pairs()));
+                self.status = String::from("Connecting");
+            },
+        }
+    }
+
+    /// Close the request's WebSocket session or event stream, if it has one
+    pub fn close_connection(&mut self) {
+        if let Some(session) = &self.ws_session {
+            session.close();
+        }
+
+        if let Some(stream) = &mut self.sse_stream {
+            stream.stop();
+            self.status = String::from(stream.close_reason.as_str());
+        }
+    }
+
+    /// Log frames and events received by the request's WebSocket session or
+    /// event stream since the last update
+    ///
+    /// Returns whether anything new was logged
+    pub fn update_connections(&mut self) -> bool {
+        let mut updated = false;
+
+        if let Some(session) = &mut self.ws_session {
+            if session.update() {
+                updated = true;
+                self.status = String::from(if session.is_open {
+                    "Connected"
+                } else {
+                    "Closed"
+                });
+            }
+        }
+
+        if let Some(stream) = &mut self.sse_stream {
+            if stream.update() {
+                updated = true;
+                if !stream.is_open {
+                    self.status = stream.close_reason.clone();
+                }
+            }
+        }
+
+        if let Some(loading) = &self.gql_loading {
+            match loading.try_recv() {
+                Ok(Ok(names)) => {
+                    self.resp = format!("Loaded {} GraphQL type and field \
+                        names", names.len());
+                    self.status = String::new();
+                    self.gql_schema = names;
+                    self.gql_loading = None;
+                    updated = true;
+                },
+                Ok(Err(e)) => {
+                    self.resp = e;
+                    self.status = String::from("Error");
+                    self.gql_loading = None;
+                    updated = true;
+                },
+                Err(mpsc::TryRecvError::Empty) => (),
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.gql_loading = None;
+                },
+            }
+        }
+
+        if let Some(download) = &mut self.download {
+            if download.update() {
+                updated = true;
+                self.preview = download.preview_text();
+                self.notice = download.progress();
+            }
+        }
+
+        updated
+    }
+
+    /// Text of the last response, or the start of its body while it's
+    /// downloaded
+    pub fn response_text(&self) -> &str {
+        match self.download {
+            Some(_) => &self.preview,
+            None => &self.resp,
+        }
+    }
+
+    /// Save the full body of the last response to a file in the current
+    /// directory, named after the last segment of the url path
+    ///
+    /// Files that already exist aren't overwritten. Returns the path of the
+    /// saved file
+    pub fn save_body(&self, variables: &Variables)
+        -> Result<PathBuf, Box<dyn std::error::Error>> {
+
+        let download = self.download.as_ref()
+            .ok_or("There is no response body to save")?;
+        if !download.is_done {
+            return Err("The response body is still downloading".into());
+        }
+
+        let name = reqwest::Url::parse(&self.resolve(variables).url).ok()
+            .and_then(|url| url.path_segments()
+                .and_then(|mut segments| segments.next_back()
+                    .map(|s| s.to_string())))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| String::from("response.body"));
+        let path = PathBuf::from(name);
+
+        match download.body_file.save_to(&path) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists =>
+                Err(format!("{} already exists", path.display()).into()),
+            Err(e) => Err(e.into()),
+            Ok(_) => Ok(path),
+        }
+    }
+
+    /// Start loading the type and field names of the GraphQL schema at the
+    /// request's url for completion in the query editor
+    ///
+    /// The introspection query is sent with the request's headers on its own
+    /// thread, and the names are picked up by `update_connections`
+    pub fn load_gql_schema(&mut self, variables: &Variables, config: &Config) {
+        let client = match client(config) {
+            Ok(client) => client,
+            Err(e) => {
+                self.resp = format!("{}", e);
+                self.status = String::from("Error");
+                return;
+            },
+        };
+
+        let resolved = self.resolve(variables);
+        let builder = resolved.with_headers(client.post(resolved.url.as_str()),
+            config);
+        let timeout = config.timeout;
+
+        let (sender, loading) = mpsc::channel();
+        thread::spawn(move || {
+            let names = graphql::introspect(builder, timeout)
+                .map_err(|e| format!("{}", e));
+            sender.send(names).ok();
+        });
+
+        self.gql_loading = Some(loading);
+        self.status = String::from("Loading GraphQL schema");
+    }
+
+    /// Add the headers of the request, and the default ones from the config
+    /// it doesn't set
+    fn with_headers(&self, mut builder: blocking::RequestBuilder,
+        config: &Config) -> blocking::RequestBuilder {
+
+        let headers = self.header_pairs();
+
+        // Multipart bodies set their own Content-Type with the boundary,
+        // which a second one would hide
+        let is_kept = |name: &str| {
+            self.body_mode != BodyMode::Multipart
+                || !name.eq_ignore_ascii_case("content-type")
+        };
+        for (name, value) in headers.iter()
+            .filter(|(name, _)| is_kept(name)) {
+
+            builder = builder.header(name, value);
+        }
+
+        // Default headers are only sent if the request doesn't set them
+        for (name, value) in config.headers.iter()
+            .filter(|(name, _)| is_kept(name) && !headers.iter()
+                .any(|(set, _)| set.eq_ignore_ascii_case(name))) {
+
+            builder = builder.header(name, value);
+        }
+
+        builder
+    }
+
+    /// Attach the body for the current body mode to a request builder
+    fn with_body(&self, builder: blocking::RequestBuilder)
+        -> Result<blocking::RequestBuilder, Box<dyn std::error::Error>> {
+
+        let builder = match self.body_mode {
+            BodyMode::Raw => {
+                if self.body.is_empty() {
+                    builder
+                } else if self.has_content_type() {
+                    builder.body(self.body.clone())
+                } else {
+                    builder.header(CONTENT_TYPE, self.content_type.as_str())
+                        .body(self.body.clone())
+                }
+            },
+            BodyMode::FormUrlEncoded => {
+                let pairs: Vec<(&str, &str)> = self.form.iter()
+                    .map(|field| (field.key.as_str(), field.value.as_str()))
+                    .collect();
+
+                builder.form(&pairs)
+            },
+            BodyMode::Multipart => {
+                let mut form = multipart::Form::new();
+                for field in self.form.iter() {
+                    form = match field.value.strip_prefix('@') {
+                        Some(path) => form.file(field.key.clone(), path)?,
+                        None => form.text(field.key.clone(),
+                            field.value.clone()),
+                    };
+                }
+
+                builder.multipart(form)
+            },
+            BodyMode::Binary => {
+                let file = File::open(&self.body_file)?;
+                if self.has_content_type() {
+                    builder.body(file)
+                } else {
+                    builder.header(CONTENT_TYPE, "application/octet-stream")
+                        .body(file)
+                }
+            },
+            BodyMode::GraphQl => builder.json(&self.graphql.to_json()?),
+        };
+
+        Ok(builder)
+    }
+
     /// Run the request and get the response
-    pub fn run_req(&mut self) {
-        let client = blocking::Client::new();
-
-        // Get the response text or error message if any
-        match Method::from_bytes(self.req_type.as_bytes()) {
-            Ok(method) => match client.request(method, self.url.clone())
-                .body(self.body.clone())
-                .send(){
-                    Ok(req) => {
-                        self.status = String::from(req.status().as_str().clone());
-                        self.resp = match &req.text() {
-                            Ok(text) => text.to_string(),
-                            Err(e) => format!("{}", e),
-                        };
-                    },
-                    Err(e) => {
-                        self.resp = format!("{}", e);
-                        self.status = String::from("Error");
+    ///
+    /// Takes in the variables of the active environment, and the config with
+    /// the timeouts and default headers to send with
+    pub fn run_req(&mut self, variables: &Variables, config: &Config) {
+        // Stop any earlier event stream or download from this request
+        self.sse_stream = None;
+        self.download = None;
+        self.preview = String::new();
+        self.notice = String::new();
+        self.resp_headers.clear();
+        self.checks.clear();
+
+        let client = match client(config) {
+            Ok(client) => client,
+            Err(e) => {
+                self.resp = format!("{}", e);
+                self.status = String::from("Error");
+                return;
+            },
+        };
+
+        // Build the request, then get the response text or error message if
+        // any
+        let sent = Method::from_bytes(self.req_type.as_bytes())
+            .map_err(|e| e.into())
+            .and_then(|method| {
+                let resolved = self.resolve(variables);
+                let builder = client.request(method, resolved.url.clone());
+                let builder = resolved.with_headers(builder, config);
+                resolved.with_body(builder)
+            })
+            .and_then(|builder| {
+                self.sent_at = Some(SystemTime::now());
+                let start = Instant::now();
+                let resp = send(builder, config.timeout);
+                self.wait_time = start.elapsed();
+
+                resp
+            });
+
+        match sent {
+            Ok(req) => {
+                self.status = String::from(req.status().as_str());
+                self.resp_version = format!("{:?}", req.version());
+                self.resp_headers = req.headers().iter()
+                    .map(|(name, value)| (name.to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned()))
+                    .collect();
+
+                let is_event_stream = req.headers().get(CONTENT_TYPE)
+                    .and_then(|content_type| content_type.to_str().ok())
+                    .is_some_and(|content_type| content_type
+                        .starts_with(EVENT_STREAM_TYPE));
+                if is_event_stream {
+                    self.resp = String::new();
+                    self.status = format!("{} (streaming)", self.status);
+                    self.sse_stream = Some(SseStream::start(req));
+                    return;
+                }
+
+                // Stream the body so large responses don't block the UI or
+                // fill memory
+                match Download::start(req) {
+                    Ok(download) => {
+                        self.resp = String::new();
+                        self.download = Some(download);
+                        self.checks_pending = true;
                     },
-                },
+                    Err(e) => self.resp = format!("{}", e),
+                };
+            },
             Err(e) => {
                 self.resp = format!("{}", e);
                 self.status = String::from("Error");
@@ -72,3 +882,99 @@ impl Request {
         };
     }
 }
+
+/// Build a client with the connect timeout from the config
+///
+/// The client has no timeout of its own, which would also end event streams
+/// and downloads that go quiet, so only waiting for the response is timed out
+/// by `send`
+fn client(config: &Config) -> reqwest::Result<blocking::Client> {
+    blocking::Client::builder()
+        .timeout(None)
+        .connect_timeout(config.connect_timeout)
+        .build()
+}
+
+/// Send a request on its own thread, waiting at most a timeout for the
+/// response headers so the UI isn't stuck on a server that never answers
+///
+/// A request that times out is left to finish on its thread, and its
+/// response is dropped
+pub fn send(builder: blocking::RequestBuilder, timeout: Option<Duration>)
+    -> Result<blocking::Response, Box<dyn std::error::Error>> {
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        sender.send(builder.send()).ok();
+    });
+
+    let resp = match timeout {
+        Some(timeout) => receiver.recv_timeout(timeout)
+            .map_err(|e| match e {
+                RecvTimeoutError::Timeout => format!("No response after {} \
+                    seconds", timeout.as_secs()),
+                RecvTimeoutError::Disconnected =>
+                    String::from("The request stopped without a response"),
+            })?,
+        None => receiver.recv()
+            .map_err(|_| "The request stopped without a response")?,
+    };
+
+    Ok(resp?)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{
+        blocking,
+        header::CONTENT_TYPE,
+    };
+
+    use super::{
+        BodyMode,
+        FormField,
+        Request,
+    };
+    use crate::app::config::Config;
+
+    /// Content-Type headers a request would be sent with
+    fn content_types(req: &Request, config: &Config) -> Vec<String> {
+        let builder = blocking::Client::new().post("http://localhost/");
+        let builder = req.with_headers(builder, config);
+        let sent = req.with_body(builder).unwrap().build().unwrap();
+
+        sent.headers().get_all(CONTENT_TYPE).iter()
+            .map(|value| value.to_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn raw_bodies_use_the_content_type_header_if_set() {
+        let mut req = Request::new("Raw");
+        req.body = String::from("<a/>");
+        req.content_type = String::from("application/json");
+        assert_eq!(content_types(&req, &Config::default()),
+            ["application/json"]);
+
+        req.headers = String::from("Content-Type: text/xml");
+        assert_eq!(content_types(&req, &Config::default()), ["text/xml"]);
+    }
+
+    #[test]
+    fn multipart_bodies_keep_their_own_content_type() {
+        let mut req = Request::new("Multipart");
+        req.body_mode = BodyMode::Multipart;
+        req.form = vec![FormField {
+            key: String::from("name"),
+            value: String::from("value"),
+        }];
+        req.headers = String::from("Content-Type: multipart/form-data");
+        let mut config = Config::default();
+        config.headers.push((String::from("content-type"),
+            String::from("application/json")));
+
+        let sent = content_types(&req, &config);
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].starts_with("multipart/form-data; boundary="));
+    }
+}
@@ -0,0 +1,446 @@
+//! # schema
+//!
+//! The `schema` crate contains a JSON Schema validator for response bodies.
+//! It understands the OpenAPI 3.0 `nullable` keyword, and resolves local
+//! `$ref`s against a root document so schemas inside specs can be used as is.
+
+use std::{
+    fmt,
+    path::Path,
+};
+
+use regex::Regex;
+use serde_json::{
+    Map,
+    Value,
+};
+
+use super::openapi;
+
+/// A way a value doesn't match a schema
+pub struct Violation {
+    // JSON pointer to the part of the value that doesn't match
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() { "/" } else { &self.pointer };
+        write!(f, "{}: {}", pointer, self.message)
+    }
+}
+
+/// Load a schema written inline as JSON, or from the JSON or YAML file at a
+/// path
+pub fn load(text: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let text = text.trim();
+
+    if text.starts_with('{') || text == "true" || text == "false" {
+        Ok(serde_json::from_str(text)?)
+    } else {
+        openapi::load_document(Path::new(text))
+    }
+}
+
+/// Validate a value against a schema
+///
+/// `root` is the document `$ref`s in the schema are resolved against, which is
+/// the schema itself for standalone schemas. Returns every violation found
+pub fn validate(root: &Value, schema: &Value, instance: &Value)
+    -> Vec<Violation> {
+
+    let mut violations = Vec::new();
+    check(root, schema, instance, "", &mut violations);
+    violations
+}
+
+/// Check a value against a schema, adding violations to the list
+fn check(root: &Value, schema: &Value, instance: &Value, pointer: &str,
+    violations: &mut Vec<Violation>) {
+
+    let mut add = |message: String| violations.push(Violation {
+        pointer: pointer.to_string(),
+        message,
+    });
+
+    let schema = match openapi::resolve(root, schema) {
+        Value::Bool(false) => return add(String::from("No value is allowed")),
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+
+    if instance.is_null() && schema.get("nullable") == Some(&Value::Bool(true)) {
+        return;
+    }
+
+    if let Some(schema_type) = schema.get("type") {
+        let types: Vec<&str> = match schema_type {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter()
+                .filter_map(|name| name.as_str())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        if !types.is_empty()
+            && !types.iter().any(|name| is_type(instance, name)) {
+
+            return add(format!("Expected {}, found {}", types.join(" or "),
+                type_name(instance)));
+        }
+    }
+
+    if let Some(values) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !values.contains(instance) {
+            add(format!("{} isn't one of the allowed values", instance));
+        }
+    }
+
+    if let Some(value) = schema.get("const") {
+        if value != instance {
+            add(format!("Expected {}", value));
+        }
+    }
+
+    match instance {
+        Value::Object(object) =>
+            check_object(root, schema, object, pointer, violations),
+        Value::Array(items) =>
+            check_array(root, schema, items, pointer, violations),
+        Value::String(text) => check_string(schema, text, pointer, violations),
+        Value::Number(number) => check_number(schema,
+            number.as_f64().unwrap_or_default(), pointer, violations),
+        _ => (),
+    }
+
+    check_combinators(root, schema, instance, pointer, violations);
+}
+
+/// Check the properties of an object
+fn check_object(root: &Value, schema: &Map<String, Value>,
+    object: &Map<String, Value>, pointer: &str,
+    violations: &mut Vec<Violation>) {
+
+    if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+        for name in required.iter().filter_map(|name| name.as_str()) {
+            if !object.contains_key(name) {
+                violations.push(violation(pointer,
+                    format!("Missing required property \"{}\"", name)));
+            }
+        }
+    }
+
+    let len = object.len() as u64;
+    if let Some(min) = schema.get("minProperties").and_then(|n| n.as_u64()) {
+        if len < min {
+            violations.push(violation(pointer,
+                format!("Expected at least {} properties", min)));
+        }
+    }
+    if let Some(max) = schema.get("maxProperties").and_then(|n| n.as_u64()) {
+        if len > max {
+            violations.push(violation(pointer,
+                format!("Expected at most {} properties", max)));
+        }
+    }
+
+    let properties = schema.get("properties").and_then(|p| p.as_object());
+    let patterns: Vec<(Regex, &Value)> = schema.get("patternProperties")
+        .and_then(|p| p.as_object())
+        .map(|patterns| patterns.iter()
+            .filter_map(|(pattern, schema)| Regex::new(pattern).ok()
+                .map(|regex| (regex, schema)))
+            .collect())
+        .unwrap_or_default();
+
+    for (name, value) in object {
+        let child = format!("{}/{}", pointer, escape(name));
+        let mut matched = false;
+
+        if let Some(property) = properties.and_then(|p| p.get(name)) {
+            matched = true;
+            check(root, property, value, &child, violations);
+        }
+
+        for (regex, property) in patterns.iter() {
+            if regex.is_match(name) {
+                matched = true;
+                check(root, property, value, &child, violations);
+            }
+        }
+
+        // Properties not covered above are checked by additionalProperties
+        match schema.get("additionalProperties") {
+            _ if matched => (),
+            Some(Value::Bool(false)) => violations.push(violation(&child,
+                format!("Property \"{}\" isn't allowed", name))),
+            Some(additional) =>
+                check(root, additional, value, &child, violations),
+            None => (),
+        }
+    }
+}
+
+/// Check the items of an array
+fn check_array(root: &Value, schema: &Map<String, Value>, items: &[Value],
+    pointer: &str, violations: &mut Vec<Violation>) {
+
+    let len = items.len() as u64;
+    if let Some(min) = schema.get("minItems").and_then(|n| n.as_u64()) {
+        if len < min {
+            violations.push(violation(pointer,
+                format!("Expected at least {} items", min)));
+        }
+    }
+    if let Some(max) = schema.get("maxItems").and_then(|n| n.as_u64()) {
+        if len > max {
+            violations.push(violation(pointer,
+                format!("Expected at most {} items", max)));
+        }
+    }
+
+    if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+        let has_duplicates = items.iter().enumerate()
+            .any(|(i, item)| items[..i].contains(item));
+        if has_duplicates {
+            violations.push(violation(pointer,
+                String::from("Items aren't unique")));
+        }
+    }
+
+    match schema.get("items") {
+        // A list of schemas checks the items in the same positions
+        Some(Value::Array(schemas)) => {
+            for (i, (item, item_schema)) in items.iter()
+                .zip(schemas.iter()).enumerate() {
+
+                check(root, item_schema, item, &format!("{}/{}", pointer, i),
+                    violations);
+            }
+        },
+        Some(item_schema) => {
+            for (i, item) in items.iter().enumerate() {
+                check(root, item_schema, item, &format!("{}/{}", pointer, i),
+                    violations);
+            }
+        },
+        None => (),
+    }
+}
+
+/// Check the length and pattern of a string
+fn check_string(schema: &Map<String, Value>, text: &str, pointer: &str,
+    violations: &mut Vec<Violation>) {
+
+    let len = text.chars().count() as u64;
+    if let Some(min) = schema.get("minLength").and_then(|n| n.as_u64()) {
+        if len < min {
+            violations.push(violation(pointer,
+                format!("Expected at least {} characters", min)));
+        }
+    }
+    if let Some(max) = schema.get("maxLength").and_then(|n| n.as_u64()) {
+        if len > max {
+            violations.push(violation(pointer,
+                format!("Expected at most {} characters", max)));
+        }
+    }
+
+    if let Some(pattern) = schema.get("pattern").and_then(|p| p.as_str()) {
+        if let Ok(regex) = Regex::new(pattern) {
+            if !regex.is_match(text) {
+                violations.push(violation(pointer,
+                    format!("Doesn't match the pattern {}", pattern)));
+            }
+        }
+    }
+}
+
+/// Check the bounds of a number
+fn check_number(schema: &Map<String, Value>, number: f64, pointer: &str,
+    violations: &mut Vec<Violation>) {
+
+    let bound = |name| schema.get(name).and_then(|n| n.as_f64());
+    // OpenAPI 3.0 and older drafts make minimum and maximum exclusive with a
+    // boolean, instead of giving the exclusive bound itself
+    let exclusive = |name| schema.get(name) == Some(&Value::Bool(true));
+
+    if let Some(min) = bound("minimum") {
+        if number < min || (exclusive("exclusiveMinimum") && number == min) {
+            violations.push(violation(pointer,
+                format!("Expected a number of at least {}", min)));
+        }
+    }
+    if let Some(min) = bound("exclusiveMinimum") {
+        if number <= min {
+            violations.push(violation(pointer,
+                format!("Expected a number greater than {}", min)));
+        }
+    }
+    if let Some(max) = bound("maximum") {
+        if number > max || (exclusive("exclusiveMaximum") && number == max) {
+            violations.push(violation(pointer,
+                format!("Expected a number of at most {}", max)));
+        }
+    }
+    if let Some(max) = bound("exclusiveMaximum") {
+        if number >= max {
+            violations.push(violation(pointer,
+                format!("Expected a number less than {}", max)));
+        }
+    }
+    if let Some(divisor) = bound("multipleOf") {
+        if divisor > 0.0 && (number / divisor).fract() != 0.0 {
+            violations.push(violation(pointer,
+                format!("Expected a multiple of {}", divisor)));
+        }
+    }
+}
+
+/// Check a value against the allOf, anyOf, oneOf and not schemas
+fn check_combinators(root: &Value, schema: &Map<String, Value>,
+    instance: &Value, pointer: &str, violations: &mut Vec<Violation>) {
+
+    if let Some(all_of) = schema.get("allOf").and_then(|a| a.as_array()) {
+        for part in all_of {
+            check(root, part, instance, pointer, violations);
+        }
+    }
+
+    let matches = |parts: &Vec<Value>| parts.iter()
+        .filter(|part| validate(root, part, instance).is_empty())
+        .count();
+
+    if let Some(any_of) = schema.get("anyOf").and_then(|a| a.as_array()) {
+        if matches(any_of) == 0 {
+            violations.push(violation(pointer,
+                String::from("Doesn't match any of the anyOf schemas")));
+        }
+    }
+
+    if let Some(one_of) = schema.get("oneOf").and_then(|o| o.as_array()) {
+        let count = matches(one_of);
+        if count != 1 {
+            violations.push(violation(pointer,
+                format!("Matches {} of the oneOf schemas instead of 1", count)));
+        }
+    }
+
+    if let Some(not) = schema.get("not") {
+        if validate(root, not, instance).is_empty() {
+            violations.push(violation(pointer,
+                String::from("Matches the schema it must not match")));
+        }
+    }
+}
+
+/// Create a violation at a pointer
+fn violation(pointer: &str, message: String) -> Violation {
+    Violation {
+        pointer: pointer.to_string(),
+        message,
+    }
+}
+
+/// Is the value of the JSON Schema type with this name?
+fn is_type(instance: &Value, name: &str) -> bool {
+    match name {
+        "null" => instance.is_null(),
+        "boolean" => instance.is_boolean(),
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        "integer" => instance.as_f64().is_some_and(|n| n.fract() == 0.0),
+        _ => true,
+    }
+}
+
+/// Name of the JSON Schema type of a value
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+    }
+}
+
+/// Escape an object key for use in a JSON pointer
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json,
+        Value,
+    };
+
+    use super::validate;
+
+    /// The pointers of the violations of a value against a standalone schema
+    fn violations(schema: &Value, instance: &Value) -> Vec<String> {
+        validate(schema, schema, instance).iter()
+            .map(|violation| violation.pointer.clone())
+            .collect()
+    }
+
+    #[test]
+    fn checks_types_and_required_properties() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {
+                "id": { "type": "integer" },
+                "name": { "type": "string" },
+            },
+        });
+
+        assert!(violations(&schema, &json!({ "id": 1, "name": "Rex" }))
+            .is_empty());
+        assert_eq!(violations(&schema, &json!({ "id": 1.5, "name": "Rex" })),
+            ["/id"]);
+        assert_eq!(violations(&schema, &json!({ "id": 1 })), [""]);
+        assert_eq!(violations(&schema, &json!([])), [""]);
+    }
+
+    #[test]
+    fn points_at_array_items() {
+        let schema = json!({
+            "type": "array",
+            "items": { "type": "string" },
+        });
+
+        assert_eq!(violations(&schema, &json!(["a", 2, "c", true])),
+            ["/1", "/3"]);
+    }
+
+    #[test]
+    fn resolves_refs_against_the_root() {
+        let root = json!({
+            "components": { "schemas": { "Pet": {
+                "type": "object",
+                "required": ["name"],
+            } } },
+        });
+        let schema = json!({ "$ref": "#/components/schemas/Pet" });
+
+        assert!(validate(&root, &schema, &json!({ "name": "Rex" })).is_empty());
+        assert_eq!(validate(&root, &schema, &json!({})).len(), 1);
+    }
+
+    #[test]
+    fn allows_null_for_nullable_schemas() {
+        let schema = json!({ "type": "string", "nullable": true });
+
+        assert!(violations(&schema, &Value::Null).is_empty());
+        assert_eq!(violations(&json!({ "type": "string" }), &Value::Null),
+            [""]);
+    }
+}
@@ -0,0 +1,254 @@
+//! # sse
+//!
+//! The `sse` crate contains structures and methods for streaming Server-Sent
+//! Events from a `text/event-stream` response on their own thread, so the UI
+//! stays responsive while the stream is open.
+
+use std::{
+    io::{
+        BufRead,
+        BufReader,
+    },
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        mpsc,
+        Arc,
+    },
+    thread,
+};
+
+use reqwest::blocking;
+
+/// Content type of Server-Sent Events responses
+pub const EVENT_STREAM_TYPE: &str = "text/event-stream";
+
+/// An event dispatched by the server
+#[derive(Default)]
+pub struct SseEvent {
+    pub id: String,
+    pub event: String,
+    pub data: String,
+}
+
+impl SseEvent {
+    /// Format the event as lines in the response view
+    pub fn to_lines(&self) -> String {
+        let event = if self.event.is_empty() { "message" } else { &self.event };
+
+        format!("id: {}  event: {}\n{}", self.id, event, self.data)
+    }
+}
+
+/// Messages from the stream thread to the UI
+enum StreamEvent {
+    Event(SseEvent),
+    Closed(String),
+}
+
+/// A response whose events are being streamed
+pub struct SseStream {
+    events: mpsc::Receiver<StreamEvent>,
+    stop: Arc<AtomicBool>,
+    pub log: Vec<SseEvent>,
+    pub is_open: bool,
+    // Why the stream ended, once it has
+    pub close_reason: String,
+}
+
+impl SseStream {
+    /// Start reading events from a response on a new thread
+    pub fn start(resp: blocking::Response) -> Self {
+        let (sender, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            let reason = read_events(BufReader::new(resp), &thread_stop,
+                &sender);
+            sender.send(StreamEvent::Closed(reason)).ok();
+        });
+
+        Self {
+            events,
+            stop,
+            log: Vec::new(),
+            is_open: true,
+            close_reason: String::new(),
+        }
+    }
+
+    /// Stop reading the stream
+    ///
+    /// The stream thread exits when it next hears from the server, but no
+    /// more events are shown from now on
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if self.is_open {
+            self.is_open = false;
+            self.close_reason = String::from("Stopped");
+        }
+    }
+
+    /// Move events from the stream thread into the log
+    ///
+    /// Returns whether anything new was logged
+    pub fn update(&mut self) -> bool {
+        if !self.is_open {
+            return false;
+        }
+
+        let mut updated = false;
+        while let Ok(event) = self.events.try_recv() {
+            updated = true;
+            match event {
+                StreamEvent::Event(event) => self.log.push(event),
+                StreamEvent::Closed(reason) => {
+                    self.is_open = false;
+                    self.close_reason = reason;
+                },
+            }
+        }
+
+        updated
+    }
+}
+
+impl Drop for SseStream {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Parse events from the response line by line until it ends or the stream is
+/// stopped
+///
+/// Returns why the stream ended
+fn read_events(reader: impl BufRead, stop: &AtomicBool,
+    sender: &mpsc::Sender<StreamEvent>) -> String {
+
+    let mut event = SseEvent::default();
+    let mut data_lines: Vec<String> = Vec::new();
+    // The last event id carries over to events that don't set their own
+    let mut last_id = String::new();
+
+    for line in reader.lines() {
+        if stop.load(Ordering::Relaxed) {
+            return String::from("Stopped");
+        }
+
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => return format!("{}", e),
+        };
+
+        // A blank line dispatches the event built up so far
+        if line.is_empty() {
+            if !data_lines.is_empty() {
+                event.id = last_id.clone();
+                event.data = data_lines.join("\n");
+                data_lines.clear();
+
+                if sender.send(StreamEvent::Event(event)).is_err() {
+                    return String::from("Stopped");
+                }
+            }
+
+            event = SseEvent::default();
+            continue;
+        }
+
+        // Lines starting with a colon are comments
+        if line.starts_with(':') {
+            continue;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field,
+                value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_str(), ""),
+        };
+
+        match field {
+            "id" => last_id = value.to_string(),
+            "event" => event.event = value.to_string(),
+            "data" => data_lines.push(value.to_string()),
+            _ => (),
+        }
+    }
+
+    String::from("Stream ended")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::AtomicBool,
+        mpsc,
+    };
+
+    use super::{
+        read_events,
+        SseEvent,
+        StreamEvent,
+    };
+
+    /// Read a stream and get the events it dispatched, and why it ended
+    fn events(stream: &str) -> (Vec<SseEvent>, String) {
+        let (sender, receiver) = mpsc::channel();
+        let reason = read_events(stream.as_bytes(), &AtomicBool::new(false),
+            &sender);
+        drop(sender);
+
+        let events = receiver.iter()
+            .filter_map(|event| match event {
+                StreamEvent::Event(event) => Some(event),
+                StreamEvent::Closed(_) => None,
+            })
+            .collect();
+
+        (events, reason)
+    }
+
+    #[test]
+    fn carries_the_last_id_over_but_not_the_event_type() {
+        let (events, reason) = events("event: add\nid: 1\ndata: a\n\n\
+            data: b\n\n");
+
+        assert_eq!(reason, "Stream ended");
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].id.as_str(), events[0].event.as_str()),
+            ("1", "add"));
+        assert_eq!((events[1].id.as_str(), events[1].event.as_str()),
+            ("1", ""));
+    }
+
+    #[test]
+    fn joins_data_lines() {
+        let (events, _) = events("data: first\ndata:second\ndata\n\n");
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "first\nsecond\n");
+    }
+
+    #[test]
+    fn skips_comments_and_retry_fields() {
+        let (events, _) = events(": keep alive\n\nretry: 1000\n\n\
+            : note\ndata: a\nretry: 10\n\n");
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "a");
+        assert_eq!(events[0].to_lines(), "id:   event: message\na");
+    }
+
+    #[test]
+    fn drops_an_event_without_a_final_blank_line() {
+        let (events, reason) = events("data: a\n\ndata: b\n");
+
+        assert_eq!(reason, "Stream ended");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "a");
+    }
+}
@@ -0,0 +1,499 @@
+//! # storage
+//!
+//! The `storage` crate contains the trait for places requests and
+//! environments are saved, and its implementations. The default keeps every
+//! item in one JSON database, the files backend writes each request to its own
+//! file so collections can be committed and reviewed, and the memory backend
+//! doesn't touch the disk.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use jfs::Store;
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+use serde_json::Value;
+
+use super::{
+    environment::Environment,
+    request::Request,
+};
+
+/// Name of the workspace directory that switches it to the files backend
+pub const REQUESTS_DIR: &str = "requests";
+
+/// Fields left out of request files because they change every time a request
+/// is run, or are worked out when loading
+pub const UNTRACKED_FIELDS: [&str; 3] = ["resp", "status", "db_id"];
+
+/// Somewhere requests or environments are saved
+pub trait Storage<T> {
+    /// Load all saved items, by id
+    fn all(&self) -> io::Result<BTreeMap<String, T>>;
+
+    /// Save an item, giving it an id if it doesn't have one yet
+    ///
+    /// Returns the id the item is saved under, which can change when it's
+    /// saved again
+    fn save(&mut self, item: &T) -> io::Result<String>;
+
+    /// Delete the item with an id
+    fn delete(&mut self, id: &str) -> io::Result<()>;
+}
+
+/// Something saved in storage, which knows the id it was saved under
+pub trait Record {
+    fn db_id(&self) -> &str;
+}
+
+impl Record for Request {
+    fn db_id(&self) -> &str {
+        &self.db_id
+    }
+}
+
+impl Record for Environment {
+    fn db_id(&self) -> &str {
+        &self.db_id
+    }
+}
+
+/// Open the request storage of a workspace
+///
+/// Requests are saved to files if the workspace has a `requests` directory,
+/// and to a single JSON database otherwise
+pub fn open_requests(workspace: &Path)
+    -> io::Result<Box<dyn Storage<Request>>> {
+
+    let requests_dir = workspace.join(REQUESTS_DIR);
+    if requests_dir.is_dir() {
+        Ok(Box::new(FileStore { dir: requests_dir }))
+    } else {
+        Ok(Box::new(JsonStore::open(&workspace.join("data"))?))
+    }
+}
+
+/// Open the storage of the request templates of a workspace
+pub fn open_templates(workspace: &Path)
+    -> io::Result<Box<dyn Storage<Request>>> {
+
+    Ok(Box::new(JsonStore::open(&workspace.join("templates"))?))
+}
+
+/// Open the environment storage of a workspace
+pub fn open_environments(workspace: &Path)
+    -> io::Result<Box<dyn Storage<Environment>>> {
+
+    Ok(Box::new(JsonStore::open(&workspace.join("environments"))?))
+}
+
+/// Saves every item in a single JSON database, with random ids
+pub struct JsonStore {
+    store: Store,
+}
+
+impl JsonStore {
+    /// Open the database at a path, which gets a `.json` extension
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let db_config = jfs::Config {
+            single: true,
+            ..jfs::Config::default()
+        };
+
+        Ok(Self {
+            store: Store::new_with_cfg(path, db_config)?,
+        })
+    }
+}
+
+impl<T: Record + Serialize + DeserializeOwned> Storage<T> for JsonStore {
+    fn all(&self) -> io::Result<BTreeMap<String, T>> {
+        self.store.all()
+    }
+
+    fn save(&mut self, item: &T) -> io::Result<String> {
+        if item.db_id().is_empty() {
+            self.store.save(item)
+        } else {
+            self.store.save_with_id(item, item.db_id())
+        }
+    }
+
+    fn delete(&mut self, id: &str) -> io::Result<()> {
+        self.store.delete(id)
+    }
+}
+
+/// Keeps items in memory as JSON, so they're saved and loaded the same way
+/// as with the other stores without touching the disk
+#[derive(Default)]
+pub struct MemoryStore {
+    items: BTreeMap<String, Value>,
+    // Used to give each item a unique id
+    next_id: usize,
+}
+
+impl<T: Record + Serialize + DeserializeOwned> Storage<T> for MemoryStore {
+    fn all(&self) -> io::Result<BTreeMap<String, T>> {
+        self.items.iter()
+            .map(|(id, value)| Ok((id.clone(),
+                serde_json::from_value(value.clone())?)))
+            .collect()
+    }
+
+    fn save(&mut self, item: &T) -> io::Result<String> {
+        let id = if item.db_id().is_empty() {
+            self.next_id += 1;
+            self.next_id.to_string()
+        } else {
+            item.db_id().to_string()
+        };
+
+        self.items.insert(id.clone(), serde_json::to_value(item)?);
+        Ok(id)
+    }
+
+    fn delete(&mut self, id: &str) -> io::Result<()> {
+        self.items.remove(id)
+            .map(|_| ())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound,
+                format!("There is no item with the id {}", id)))
+    }
+}
+
+/// Saves each request as a pretty JSON file named after it, with keys in a
+/// stable order
+///
+/// Ids are the paths of the files relative to the directory, without the
+/// extension, like `folder/request-name`
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl Storage<Request> for FileStore {
+    fn all(&self) -> io::Result<BTreeMap<String, Request>> {
+        let mut requests = BTreeMap::new();
+        let mut dirs = vec![self.dir.clone()];
+
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path.extension().is_some_and(|ext| ext == "json") {
+                    let text = fs::read_to_string(&path)?;
+                    let mut req: Request = serde_json::from_str(&text)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData,
+                            format!("{}: {}", path.display(), e)))?;
+
+                    req.db_id = self.id_of(&path);
+                    requests.insert(req.db_id.clone(), req);
+                }
+            }
+        }
+
+        Ok(requests)
+    }
+
+    /// Write a request to the file named after it, removing its old file if
+    /// it was renamed or moved to another folder
+    fn save(&mut self, req: &Request) -> io::Result<String> {
+        let mut id = self.id_for(req, 1);
+        let mut attempt = 1;
+        while id != req.db_id && self.path_of(&id).exists() {
+            attempt += 1;
+            id = self.id_for(req, attempt);
+        }
+
+        let mut value = serde_json::to_value(req)?;
+        if let Value::Object(fields) = &mut value {
+            for field in UNTRACKED_FIELDS {
+                fields.remove(field);
+            }
+        }
+
+        let path = self.path_of(&id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&value)? + "\n")?;
+
+        if !req.db_id.is_empty() && req.db_id != id {
+            self.delete(&req.db_id)?;
+        }
+
+        Ok(id)
+    }
+
+    /// Delete a request's file, and its folder's directory if it's empty
+    fn delete(&mut self, id: &str) -> io::Result<()> {
+        let path = self.path_of(id);
+        fs::remove_file(&path)?;
+
+        if let Some(parent) = path.parent().filter(|dir| *dir != self.dir) {
+            // Fails if other requests are still in the folder
+            fs::remove_dir(parent).ok();
+        }
+
+        Ok(())
+    }
+}
+
+impl FileStore {
+    /// The id for a request's name and folder
+    ///
+    /// Later attempts add a number, for requests with the same name
+    fn id_for(&self, req: &Request, attempt: usize) -> String {
+        let mut name = slug(&req.name);
+        if attempt > 1 {
+            name = format!("{}-{}", name, attempt);
+        }
+
+        if req.folder.is_empty() {
+            name
+        } else {
+            format!("{}/{}", slug(&req.folder), name)
+        }
+    }
+
+    /// Path of the file for an id
+    fn path_of(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Id of the request in a file
+    fn id_of(&self, path: &Path) -> String {
+        path.strip_prefix(&self.dir)
+            .unwrap_or(path)
+            .with_extension("")
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// Turn a name into lowercase words separated by dashes, for file names
+fn slug(name: &str) -> String {
+    let slug = name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if slug.is_empty() {
+        String::from("request")
+    } else {
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        fs,
+        process,
+    };
+
+    use serde_json::Value;
+
+    use super::{
+        FileStore,
+        Request,
+        Storage,
+        UNTRACKED_FIELDS,
+    };
+    use crate::app::test_util::{
+        app,
+        listed,
+        press,
+        saved,
+    };
+
+    /// A file store in a new temporary directory for a test
+    fn file_store(test: &str) -> FileStore {
+        let dir = env::temp_dir()
+            .join(format!("almagro-test-{}-{}", process::id(), test));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+
+        FileStore { dir }
+    }
+
+    /// Ids of the requests in a file store
+    fn ids(store: &FileStore) -> Vec<String> {
+        store.all().unwrap().into_keys().collect()
+    }
+
+    #[test]
+    fn loads_saved_requests() {
+        let app = app(&["Pets", "Store"]);
+
+        assert_eq!(listed(&app), ["Pets", "Store"]);
+        assert_eq!(app.requests[0].db_id, "1");
+        assert_eq!(app.requests[1].db_id, "2");
+    }
+
+    #[test]
+    fn saves_created_requests() {
+        let mut app = app(&[]);
+        press(&mut app, 'n');
+        press(&mut app, 'n');
+
+        assert_eq!(listed(&app), ["Request #1", "Request #2"]);
+        assert_eq!(saved(&app), ["Request #1", "Request #2"]);
+
+        press(&mut app, 'u');
+        assert_eq!(saved(&app), ["Request #1"]);
+    }
+
+    #[test]
+    fn saves_renamed_requests_under_the_same_id() {
+        let mut app = app(&["Pets"]);
+        app.run_command("rename Dogs").unwrap();
+
+        assert_eq!(saved(&app), ["Dogs"]);
+        assert_eq!(app.requests[0].db_id, "1");
+    }
+
+    #[test]
+    fn deletes_requests_until_undone() {
+        let mut app = app(&["Pets", "Store"]);
+        press(&mut app, 'x');
+
+        assert_eq!(listed(&app), ["Store"]);
+        assert_eq!(saved(&app), ["Store"]);
+
+        press(&mut app, 'u');
+        assert_eq!(listed(&app), ["Pets", "Store"]);
+        assert_eq!(saved(&app), ["Pets", "Store"]);
+    }
+
+    #[test]
+    fn reorders_requests() {
+        let mut app = app(&["Pets", "Store", "Users"]);
+        press(&mut app, 'J');
+
+        assert_eq!(listed(&app), ["Store", "Pets", "Users"]);
+        assert_eq!(app.req_index, 1);
+
+        press(&mut app, 'J');
+        press(&mut app, 'K');
+        assert_eq!(listed(&app), ["Store", "Pets", "Users"]);
+
+        press(&mut app, 'u');
+        press(&mut app, 'u');
+        press(&mut app, 'u');
+        assert_eq!(listed(&app), ["Pets", "Store", "Users"]);
+        assert_eq!(saved(&app), ["Pets", "Store", "Users"]);
+    }
+
+    #[test]
+    fn files_are_named_after_new_requests() {
+        let mut store = file_store("files-named");
+        let mut req = Request::new("List Pets!");
+        req.db_id = store.save(&req).unwrap();
+
+        assert_eq!(req.db_id, "list-pets");
+        assert!(store.dir.join("list-pets.json").is_file());
+        assert_eq!(store.all().unwrap()["list-pets"].name, "List Pets!");
+
+        fs::remove_dir_all(&store.dir).ok();
+    }
+
+    #[test]
+    fn renaming_removes_the_old_file() {
+        let mut store = file_store("files-renamed");
+        let mut req = Request::new("Pets");
+        req.db_id = store.save(&req).unwrap();
+
+        req.name = String::from("Dogs");
+        req.db_id = store.save(&req).unwrap();
+
+        assert_eq!(req.db_id, "dogs");
+        assert_eq!(ids(&store), ["dogs"]);
+        assert!(!store.dir.join("pets.json").exists());
+
+        fs::remove_dir_all(&store.dir).ok();
+    }
+
+    #[test]
+    fn moving_removes_the_empty_folder() {
+        let mut store = file_store("files-moved");
+        let mut req = Request::new("List");
+        req.folder = String::from("Pets");
+        req.db_id = store.save(&req).unwrap();
+        assert_eq!(req.db_id, "pets/list");
+
+        req.folder = String::from("Store");
+        req.db_id = store.save(&req).unwrap();
+
+        assert_eq!(ids(&store), ["store/list"]);
+        assert!(!store.dir.join("pets").exists());
+
+        fs::remove_dir_all(&store.dir).ok();
+    }
+
+    #[test]
+    fn requests_with_the_same_name_are_numbered() {
+        let mut store = file_store("files-numbered");
+        let first = store.save(&Request::new("Pets")).unwrap();
+        let mut second = Request::new("pets");
+        second.db_id = store.save(&second).unwrap();
+
+        assert_eq!((first.as_str(), second.db_id.as_str()), ("pets", "pets-2"));
+
+        // Saving again keeps the number
+        assert_eq!(store.save(&second).unwrap(), "pets-2");
+        assert_eq!(ids(&store), ["pets", "pets-2"]);
+
+        fs::remove_dir_all(&store.dir).ok();
+    }
+
+    #[test]
+    fn files_leave_out_untracked_fields() {
+        let mut store = file_store("files-untracked");
+        let mut req = Request::new("Pets");
+        req.resp = String::from("[]");
+        req.status = String::from("200");
+        req.db_id = store.save(&req).unwrap();
+
+        let text = fs::read_to_string(store.dir.join("pets.json")).unwrap();
+        let saved: Value = serde_json::from_str(&text).unwrap();
+        for field in UNTRACKED_FIELDS {
+            assert!(saved.get(field).is_none(), "{} was saved", field);
+        }
+
+        let loaded = &store.all().unwrap()["pets"];
+        assert_eq!(loaded.db_id, "pets");
+        assert!(loaded.resp.is_empty());
+
+        fs::remove_dir_all(&store.dir).ok();
+    }
+
+    #[test]
+    fn invalid_files_are_reported_with_their_path() {
+        let store = file_store("files-invalid");
+        let path = store.dir.join("broken.json");
+        fs::write(&path, "{").unwrap();
+
+        let error = match store.all() {
+            Ok(_) => panic!("Loaded an invalid file"),
+            Err(e) => e.to_string(),
+        };
+        assert!(error.starts_with(&path.display().to_string()), "{}", error);
+
+        fs::remove_dir_all(&store.dir).ok();
+    }
+}
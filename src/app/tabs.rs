@@ -0,0 +1,252 @@
+//! # tabs
+//!
+//! `tabs` keeps several requests open at once, each in a tab shown above the
+//! info panel. The selected tab's request, field and scroll offset are the
+//! app's own, and the other tabs remember theirs until they're selected
+//! again.
+
+use super::App;
+
+/// A request open in a tab, and where it was left
+#[derive(Clone, Copy, Default)]
+pub struct Tab {
+    pub req_index: usize,
+    // The selected field, and how far it's scrolled
+    input_index: usize,
+    text_offset: (u16, u16),
+}
+
+/// The open tabs, and which one is selected
+pub struct Tabs {
+    pub tabs: Vec<Tab>,
+    pub active: usize,
+}
+
+impl Default for Tabs {
+    fn default() -> Self {
+        Self {
+            tabs: vec![Tab::default()],
+            active: 0,
+        }
+    }
+}
+
+impl App {
+    /// Open the selected request in a new tab after the current one
+    pub fn open_tab(&mut self) {
+        self.save_tab();
+
+        let tabs = &mut self.tabs;
+        tabs.active += 1;
+        tabs.tabs.insert(tabs.active, Tab {
+            req_index: self.req_index,
+            ..Tab::default()
+        });
+        self.load_tab();
+    }
+
+    /// Select the next tab, or the previous one, wrapping around
+    pub fn switch_tab(&mut self, forward: bool) {
+        let len = self.tabs.tabs.len();
+        if len < 2 {
+            return;
+        }
+
+        self.save_tab();
+        self.tabs.active = if forward {
+            (self.tabs.active + 1) % len
+        } else {
+            (self.tabs.active + len - 1) % len
+        };
+        self.load_tab();
+    }
+
+    /// Close the selected tab, unless it's the last one
+    pub fn close_tab(&mut self) -> Result<(), String> {
+        let tabs = &mut self.tabs;
+        if tabs.tabs.len() < 2 {
+            return Err(String::from("Can't close the last tab"));
+        }
+
+        tabs.tabs.remove(tabs.active);
+        tabs.active = tabs.active.min(tabs.tabs.len() - 1);
+        self.load_tab();
+        Ok(())
+    }
+
+    /// Index of the request open in each tab
+    pub fn tab_requests(&self) -> Vec<usize> {
+        self.tabs.tabs.iter()
+            .enumerate()
+            .map(|(index, tab)| if index == self.tabs.active {
+                self.req_index
+            } else {
+                tab.req_index
+            })
+            .collect()
+    }
+
+    /// Keep the other tabs on their requests when a request is inserted
+    /// into the list
+    pub fn tabs_inserted(&mut self, index: usize) {
+        for tab in self.other_tabs() {
+            if tab.req_index >= index {
+                tab.req_index += 1;
+            }
+        }
+    }
+
+    /// Keep the other tabs on their requests when a request is removed from
+    /// the list, closing the tabs it was open in
+    pub fn tabs_removed(&mut self, index: usize) {
+        let selected = self.tabs.active;
+        let mut active = selected;
+        let mut position = 0;
+        self.tabs.tabs.retain(|tab| {
+            let keep = position == selected || tab.req_index != index;
+            if !keep && position < selected {
+                active -= 1;
+            }
+            position += 1;
+            keep
+        });
+        self.tabs.active = active;
+
+        for tab in self.other_tabs() {
+            if tab.req_index > index {
+                tab.req_index -= 1;
+            }
+        }
+    }
+
+    /// Keep the other tabs on their requests when two requests swap places
+    /// in the list
+    pub fn tabs_swapped(&mut self, a: usize, b: usize) {
+        for tab in self.other_tabs() {
+            if tab.req_index == a {
+                tab.req_index = b;
+            } else if tab.req_index == b {
+                tab.req_index = a;
+            }
+        }
+    }
+
+    /// The tabs that aren't selected
+    fn other_tabs(&mut self) -> impl Iterator<Item = &mut Tab> {
+        let active = self.tabs.active;
+        self.tabs.tabs.iter_mut()
+            .enumerate()
+            .filter(move |(index, _)| *index != active)
+            .map(|(_, tab)| tab)
+    }
+
+    /// Remember where the selected tab was left
+    fn save_tab(&mut self) {
+        self.tabs.tabs[self.tabs.active] = Tab {
+            req_index: self.req_index,
+            input_index: self.input_index,
+            text_offset: self.text_offset,
+        };
+    }
+
+    /// Go back to where the selected tab was left
+    fn load_tab(&mut self) {
+        let tab = self.tabs.tabs[self.tabs.active];
+        self.req_index = tab.req_index
+            .min(self.requests.len().saturating_sub(1));
+        self.input_index = tab.input_index;
+        self.text_offset = tab.text_offset;
+        self.update_inputs();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::{
+        request::Request,
+        test_util::app,
+        App,
+    };
+
+    /// Open a new tab, and select the request at an index in it
+    fn open(app: &mut App, index: usize) {
+        app.open_tab();
+        app.req_index = index;
+    }
+
+    /// Names of the requests open in each tab
+    fn tab_names(app: &App) -> Vec<String> {
+        app.tab_requests().into_iter()
+            .map(|index| app.requests[index].name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn opens_and_switches_tabs() {
+        let mut app = app(&["a", "b", "c"]);
+        open(&mut app, 2);
+        app.input_index = 3;
+
+        assert_eq!(tab_names(&app), ["a", "c"]);
+        assert_eq!(app.tabs.active, 1);
+
+        app.switch_tab(true);
+        assert_eq!(app.tabs.active, 0);
+        assert_eq!(app.req_index, 0);
+        assert_eq!(app.input_index, 0);
+
+        // Each tab goes back to the field it was left on
+        app.switch_tab(false);
+        assert_eq!(app.req_index, 2);
+        assert_eq!(app.input_index, 3);
+    }
+
+    #[test]
+    fn closes_tabs_but_not_the_last() {
+        let mut app = app(&["a", "b"]);
+        assert!(app.close_tab().is_err());
+
+        open(&mut app, 1);
+        app.close_tab().unwrap();
+        assert_eq!(tab_names(&app), ["a"]);
+        assert_eq!(app.req_index, 0);
+    }
+
+    #[test]
+    fn follows_requests_when_the_list_changes() {
+        let mut app = app(&["a", "b", "c"]);
+        open(&mut app, 1);
+        open(&mut app, 2);
+        open(&mut app, 0);
+        assert_eq!(tab_names(&app), ["a", "b", "c", "a"]);
+
+        app.create_request(None);
+        app.req_index = 0;
+        assert_eq!(tab_names(&app), ["a", "b", "c", "a"]);
+
+        app.requests.insert(0, Request::new("new"));
+        app.tabs_inserted(0);
+        app.req_index = 1;
+        assert_eq!(tab_names(&app), ["a", "b", "c", "a"]);
+
+        app.requests.swap(2, 3);
+        app.tabs_swapped(2, 3);
+        assert_eq!(tab_names(&app), ["a", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn closes_the_tabs_of_deleted_requests() {
+        let mut app = app(&["a", "b", "c"]);
+        open(&mut app, 1);
+        open(&mut app, 2);
+        open(&mut app, 1);
+        open(&mut app, 2);
+        assert_eq!(tab_names(&app), ["a", "b", "c", "b", "c"]);
+        assert_eq!(app.tabs.active, 4);
+
+        // The selected tab stays open, on the request before the deleted one
+        app.delete_request();
+        assert_eq!(tab_names(&app), ["a", "b", "b", "b"]);
+        assert_eq!(app.tabs.active, 3);
+    }
+}
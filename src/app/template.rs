@@ -0,0 +1,93 @@
+//! # template
+//!
+//! `template` implements methods to save requests as templates, like a preset
+//! method, auth headers and body, and to create new requests from them.
+
+use super::{
+    history::{
+        Change,
+        Snapshot,
+    },
+    picker::{
+        Picker,
+        PickerAction,
+    },
+    request::Request,
+    App,
+};
+
+impl App {
+    /// Ask which template to start a new request from, or create a blank
+    /// request straight away if there are no templates
+    pub fn new_request(&mut self) {
+        if self.templates.is_empty() {
+            self.create_request(None);
+        } else {
+            self.open_template_picker();
+        }
+    }
+
+    /// Open the picker of templates for a new request
+    pub fn open_template_picker(&mut self) {
+        let items = std::iter::once(String::from("Blank request"))
+            .chain(self.templates.iter().map(|template| template.name.clone()))
+            .collect();
+
+        self.picker = Some(Picker::new("New request from (x deletes a \
+            template)", items, PickerAction::Template));
+    }
+
+    /// Create a request at the end of the list, from the template at an index
+    /// if one is given
+    pub fn create_request(&mut self, template: Option<usize>) {
+        let name = format!("Request #{}", self.requests.len() + 1);
+        let template = template.and_then(|index| self.templates.get(index));
+        let new_req = match template {
+            Some(template) => {
+                let mut new_req = template.duplicate();
+                new_req.name = name;
+                new_req
+            },
+            None => Request::new(name),
+        };
+        let index = self.requests.len();
+
+        self.insert_request(index, new_req);
+        self.history.record(Change::Create(index,
+            Snapshot::of(&self.requests[index])));
+    }
+
+    /// Save a copy of the selected request as a template
+    pub fn save_template(&mut self) {
+        let mut template = match self.requests.get(self.req_index) {
+            Some(cur_req) => cur_req.duplicate(),
+            None => return,
+        };
+
+        // Save once to get the ID, then again to store it with the template
+        let saved = self.template_db.save(&template)
+            .and_then(|id| {
+                template.db_id = id;
+                self.template_db.save(&template)
+            });
+
+        match saved {
+            Ok(_) => {
+                self.status_line = format!("Saved \"{}\" as a template",
+                    template.name);
+                self.templates.push(template);
+            },
+            Err(e) => self.status_line = format!("Couldn't save the \
+                template: {}", e),
+        }
+    }
+
+    /// Delete the template at an index
+    pub fn delete_template(&mut self, index: usize) {
+        let template = self.templates.remove(index);
+
+        if let Err(e) = self.template_db.delete(&template.db_id) {
+            self.status_line = format!("Couldn't delete the template: {}", e);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! # test_util
+//!
+//! The `test_util` crate contains helpers shared by the unit tests of the
+//! app, to build an app that keeps everything in memory and look at its
+//! requests.
+
+use std::env;
+
+use crossterm::event::{
+    Event,
+    KeyCode,
+    KeyEvent,
+    KeyModifiers,
+};
+
+use super::{
+    request::Request,
+    storage::{
+        MemoryStore,
+        Storage,
+    },
+    App,
+};
+
+/// An app that keeps its requests in memory, starting with `requests`
+pub fn app(requests: &[&str]) -> App {
+    // Requests are saved with the id they're given, like the app does
+    let mut db = MemoryStore::default();
+    for name in requests {
+        let mut req = Request::new(name);
+        req.db_id = db.save(&req).unwrap();
+        db.save(&req).unwrap();
+    }
+
+    App::with_storage(env::temp_dir(), Box::new(db),
+        Box::new(MemoryStore::default()),
+        Box::new(MemoryStore::default())).unwrap()
+}
+
+/// Press a character key, holding shift for uppercase letters
+pub fn press(app: &mut App, c: char) {
+    let modifiers = if c.is_ascii_uppercase() {
+        KeyModifiers::SHIFT
+    } else {
+        KeyModifiers::NONE
+    };
+    app.handle_keys(Event::Key(KeyEvent {
+        code: KeyCode::Char(c),
+        modifiers,
+    }));
+}
+
+/// Names of the requests in the list
+pub fn listed(app: &App) -> Vec<String> {
+    app.requests.iter()
+        .map(|req| req.name.clone())
+        .collect()
+}
+
+/// Names of the requests in storage, in the order of their ids
+pub fn saved(app: &App) -> Vec<String> {
+    app.db.all().unwrap()
+        .into_values()
+        .map(|req| req.name)
+        .collect()
+}
@@ -0,0 +1,517 @@
+//! # theme
+//!
+//! The `theme` crate holds the colours the UI is drawn with. There are named
+//! themes for dark and light terminals, config files can define their own,
+//! and colours are turned off when the `NO_COLOR` environment variable is set.
+
+use std::{
+    collections::BTreeMap,
+    env,
+};
+
+use serde::Deserialize;
+use tui::{
+    style::{
+        Color,
+        Modifier,
+        Style,
+    },
+    text::{
+        Span,
+        Spans,
+        Text,
+    },
+};
+
+/// Theme used unless the config file picks another
+pub const DEFAULT_THEME: &str = "dark";
+
+/// Names of the built in themes
+pub const THEMES: [&str; 3] = ["dark", "light", "mono"];
+
+/// Colours the UI is drawn with
+#[derive(Clone)]
+pub struct Theme {
+    pub text: Color,
+    pub border: Color,
+    // Colours of the selected request, field or item
+    pub selected_text: Color,
+    pub selected_bg: Color,
+    // Colour of errors and messages in the status line
+    pub error: Color,
+    // Colours of request methods
+    pub get: Color,
+    pub post: Color,
+    pub put: Color,
+    pub patch: Color,
+    pub delete: Color,
+    pub other_method: Color,
+    // Colours of response status codes, by class
+    pub success: Color,
+    pub redirect: Color,
+    pub client_error: Color,
+    pub server_error: Color,
+    // Colours of JSON response bodies
+    pub key: Color,
+    pub string: Color,
+    pub number: Color,
+    pub literal: Color,
+    pub punctuation: Color,
+    // Whether to draw any colours, which `NO_COLOR` turns off
+    pub colored: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+/// Colours a config file changes in a theme, starting from a built in one
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeFile {
+    base: Option<String>,
+    text: Option<String>,
+    border: Option<String>,
+    selected_text: Option<String>,
+    selected_bg: Option<String>,
+    error: Option<String>,
+    get: Option<String>,
+    post: Option<String>,
+    put: Option<String>,
+    patch: Option<String>,
+    delete: Option<String>,
+    other_method: Option<String>,
+    success: Option<String>,
+    redirect: Option<String>,
+    client_error: Option<String>,
+    server_error: Option<String>,
+    key: Option<String>,
+    string: Option<String>,
+    number: Option<String>,
+    literal: Option<String>,
+    punctuation: Option<String>,
+}
+
+impl Theme {
+    /// Light text with a yellow selection, for dark terminals
+    pub fn dark() -> Self {
+        Self {
+            text: Color::White,
+            border: Color::White,
+            selected_text: Color::Black,
+            selected_bg: Color::Yellow,
+            error: Color::LightRed,
+            get: Color::LightGreen,
+            post: Color::LightYellow,
+            put: Color::LightBlue,
+            patch: Color::LightMagenta,
+            delete: Color::LightRed,
+            other_method: Color::LightCyan,
+            success: Color::LightGreen,
+            redirect: Color::LightCyan,
+            client_error: Color::LightYellow,
+            server_error: Color::LightRed,
+            key: Color::LightBlue,
+            string: Color::LightGreen,
+            number: Color::LightMagenta,
+            literal: Color::LightYellow,
+            punctuation: Color::Gray,
+            colored: true,
+        }
+    }
+
+    /// Dark text with a blue selection, for light terminals
+    pub fn light() -> Self {
+        Self {
+            text: Color::Black,
+            border: Color::DarkGray,
+            selected_text: Color::White,
+            selected_bg: Color::Blue,
+            error: Color::Red,
+            get: Color::Green,
+            post: Color::Yellow,
+            put: Color::Blue,
+            patch: Color::Magenta,
+            delete: Color::Red,
+            other_method: Color::Cyan,
+            success: Color::Green,
+            redirect: Color::Cyan,
+            client_error: Color::Yellow,
+            server_error: Color::Red,
+            key: Color::Blue,
+            string: Color::Green,
+            number: Color::Magenta,
+            literal: Color::Yellow,
+            punctuation: Color::DarkGray,
+            colored: true,
+        }
+    }
+
+    /// The terminal's own colours, with the selection shown reversed
+    pub fn mono() -> Self {
+        Self {
+            text: Color::Reset,
+            border: Color::Reset,
+            selected_text: Color::Reset,
+            selected_bg: Color::Reset,
+            error: Color::Reset,
+            get: Color::Reset,
+            post: Color::Reset,
+            put: Color::Reset,
+            patch: Color::Reset,
+            delete: Color::Reset,
+            other_method: Color::Reset,
+            success: Color::Reset,
+            redirect: Color::Reset,
+            client_error: Color::Reset,
+            server_error: Color::Reset,
+            key: Color::Reset,
+            string: Color::Reset,
+            number: Color::Reset,
+            literal: Color::Reset,
+            punctuation: Color::Reset,
+            colored: false,
+        }
+    }
+
+    /// Get a built in theme by name
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "mono" => Some(Self::mono()),
+            _ => None,
+        }
+    }
+
+    /// Build the theme with a name, from the built in themes and the ones
+    /// defined in the config file
+    ///
+    /// Returns an error for unknown themes and colours. A theme defined in
+    /// the config file starts from the theme named by its `base`, or the
+    /// default theme
+    pub fn build(name: &str, defined: &BTreeMap<String, ThemeFile>)
+        -> Result<Self, String> {
+
+        let file = match defined.get(name) {
+            Some(file) => file,
+            None => return Self::named(name)
+                .ok_or(format!("Unknown theme \"{}\", the built in themes are \
+                    {}", name, THEMES.join(", "))),
+        };
+
+        let base = file.base.as_deref().unwrap_or(DEFAULT_THEME);
+        let mut theme = Self::named(base)
+            .ok_or(format!("Unknown base theme \"{}\"", base))?;
+
+        let colors = [
+            (&file.text, &mut theme.text),
+            (&file.border, &mut theme.border),
+            (&file.selected_text, &mut theme.selected_text),
+            (&file.selected_bg, &mut theme.selected_bg),
+            (&file.error, &mut theme.error),
+            (&file.get, &mut theme.get),
+            (&file.post, &mut theme.post),
+            (&file.put, &mut theme.put),
+            (&file.patch, &mut theme.patch),
+            (&file.delete, &mut theme.delete),
+            (&file.other_method, &mut theme.other_method),
+            (&file.success, &mut theme.success),
+            (&file.redirect, &mut theme.redirect),
+            (&file.client_error, &mut theme.client_error),
+            (&file.server_error, &mut theme.server_error),
+            (&file.key, &mut theme.key),
+            (&file.string, &mut theme.string),
+            (&file.number, &mut theme.number),
+            (&file.literal, &mut theme.literal),
+            (&file.punctuation, &mut theme.punctuation),
+        ];
+        for (text, color) in colors {
+            if let Some(text) = text {
+                *color = parse_color(text)?;
+            }
+        }
+
+        Ok(theme)
+    }
+
+    /// Use the terminal's own colours instead if `NO_COLOR` is set
+    ///
+    /// See <https://no-color.org>
+    pub fn honor_no_color(self) -> Self {
+        match env::var_os("NO_COLOR") {
+            Some(value) if !value.is_empty() => Self::mono(),
+            _ => self,
+        }
+    }
+
+    /// Style of normal text
+    pub fn text_style(&self) -> Style {
+        Style::default().fg(self.text)
+    }
+
+    /// Style of borders and titles
+    pub fn border_style(&self) -> Style {
+        Style::default().fg(self.border)
+    }
+
+    /// Style of the selected request, field or item
+    pub fn selected_style(&self) -> Style {
+        if self.colored {
+            Style::default()
+                .fg(self.selected_text)
+                .bg(self.selected_bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().add_modifier(Modifier::REVERSED)
+        }
+    }
+
+    /// Style of errors and messages in the status line
+    pub fn error_style(&self) -> Style {
+        Style::default().fg(self.error)
+    }
+
+    /// Style of a request method
+    pub fn method_style(&self, method: &str) -> Style {
+        let color = match method.to_uppercase().as_str() {
+            "GET" => self.get,
+            "POST" => self.post,
+            "PUT" => self.put,
+            "PATCH" => self.patch,
+            "DELETE" => self.delete,
+            _ => self.other_method,
+        };
+
+        Style::default().fg(color)
+    }
+
+    /// Style of a response status, by the class of its code
+    pub fn status_style(&self, status: &str) -> Style {
+        let color = match status.chars().next() {
+            Some('2') => self.success,
+            Some('3') => self.redirect,
+            Some('4') => self.client_error,
+            Some('5') => self.server_error,
+            _ if status.starts_with("Error") => self.error,
+            _ => self.text,
+        };
+
+        Style::default().fg(color)
+    }
+
+    /// Colour the keys, strings, numbers and literals of a JSON body
+    ///
+    /// Bodies that don't look like JSON are left as plain text
+    pub fn highlight(&self, body: &str) -> Text<'static> {
+        let trimmed = body.trim_start();
+        if !self.colored
+            || !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+
+            return Text::styled(body.to_string(), self.text_style());
+        }
+
+        Text::from(body.lines()
+            .map(|line| self.highlight_line(line))
+            .collect::<Vec<_>>())
+    }
+
+    /// Colour the tokens of one line of JSON
+    fn highlight_line(&self, line: &str) -> Spans<'static> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut spans = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let start = i;
+            let color = match chars[i] {
+                '"' => {
+                    i += 1;
+                    while i < chars.len() && chars[i] != '"' {
+                        i += if chars[i] == '\\' { 2 } else { 1 };
+                    }
+                    i = (i + 1).min(chars.len());
+
+                    // Strings followed by a colon are keys
+                    let is_key = chars[i..].iter()
+                        .find(|c| !c.is_whitespace())
+                        == Some(&':');
+                    if is_key { self.key } else { self.string }
+                },
+                c if c == '-' || c.is_ascii_digit() => {
+                    while i < chars.len() && (chars[i].is_ascii_alphanumeric()
+                        || "+-.".contains(chars[i])) {
+
+                        i += 1;
+                    }
+                    self.number
+                },
+                c if c.is_alphabetic() => {
+                    while i < chars.len() && chars[i].is_alphanumeric() {
+                        i += 1;
+                    }
+                    self.literal
+                },
+                c if "{}[],:".contains(c) => {
+                    i += 1;
+                    self.punctuation
+                },
+                _ => {
+                    while i < chars.len() && !"\"{}[],:-".contains(chars[i])
+                        && !chars[i].is_alphanumeric() {
+
+                        i += 1;
+                    }
+                    self.text
+                },
+            };
+            // Always move past at least one character
+            i = i.max(start + 1);
+
+            spans.push(Span::styled(chars[start..i].iter().collect::<String>(),
+                Style::default().fg(color)));
+        }
+
+        Spans::from(spans)
+    }
+}
+
+/// Parse a colour name like `lightblue`, or a hex colour like `#3a7bd5`
+pub fn parse_color(text: &str) -> Result<Color, String> {
+    let name = text.to_lowercase().replace(['-', '_', ' '], "");
+
+    let color = match name.as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        hex => match hex.strip_prefix('#') {
+            Some(hex) if hex.len() == 6
+                && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                let channel = |range| u8::from_str_radix(&hex[range], 16);
+                match (channel(0..2), channel(2..4), channel(4..6)) {
+                    (Ok(r), Ok(g), Ok(b)) => Color::Rgb(r, g, b),
+                    _ => return Err(format!("Unknown colour \"{}\"", text)),
+                }
+            },
+            _ => return Err(format!("Unknown colour \"{}\"", text)),
+        },
+    };
+
+    Ok(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use tui::style::Color;
+
+    use super::{
+        parse_color,
+        Theme,
+        ThemeFile,
+    };
+
+    /// Text and colour of each token highlighted in a line
+    fn tokens(line: &str) -> Vec<(String, Color)> {
+        Theme::dark().highlight_line(line).0.into_iter()
+            .map(|span| (span.content.into_owned(),
+                span.style.fg.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_names_and_hex_colours() {
+        assert_eq!(parse_color("Light-Blue"), Ok(Color::LightBlue));
+        assert_eq!(parse_color("dark grey"), Ok(Color::DarkGray));
+        assert_eq!(parse_color("#3a7BD5"), Ok(Color::Rgb(0x3a, 0x7b, 0xd5)));
+
+        for text in ["#3a7bd", "#3a7bdz", "#€abc", "3a7bd5", "teal"] {
+            assert!(parse_color(text).is_err(), "{} was parsed", text);
+        }
+    }
+
+    #[test]
+    fn builds_themes_from_a_base() {
+        let mut defined = BTreeMap::new();
+        defined.insert(String::from("paper"), ThemeFile {
+            base: Some(String::from("light")),
+            key: Some(String::from("red")),
+            ..ThemeFile::default()
+        });
+
+        let theme = Theme::build("paper", &defined).unwrap();
+        assert_eq!(theme.key, Color::Red);
+        assert_eq!(theme.text, Theme::light().text);
+        assert!(!Theme::build("mono", &defined).unwrap().colored);
+    }
+
+    #[test]
+    fn reports_unknown_themes_and_colours() {
+        let mut defined = BTreeMap::new();
+        defined.insert(String::from("odd"), ThemeFile {
+            base: Some(String::from("sepia")),
+            ..ThemeFile::default()
+        });
+        defined.insert(String::from("bad"), ThemeFile {
+            string: Some(String::from("#12345")),
+            ..ThemeFile::default()
+        });
+
+        for name in ["missing", "odd", "bad"] {
+            assert!(Theme::build(name, &defined).is_err(), "{} was built",
+                name);
+        }
+    }
+
+    #[test]
+    fn highlights_keys_and_strings() {
+        let dark = Theme::dark();
+
+        assert_eq!(tokens(r#""a\"b" : "c\"","#), [
+            (String::from(r#""a\"b""#), dark.key),
+            (String::from(" "), dark.text),
+            (String::from(":"), dark.punctuation),
+            (String::from(" "), dark.text),
+            (String::from(r#""c\"""#), dark.string),
+            (String::from(","), dark.punctuation),
+        ]);
+        assert_eq!(tokens("[-1.5e3, null]"), [
+            (String::from("["), dark.punctuation),
+            (String::from("-1.5e3"), dark.number),
+            (String::from(","), dark.punctuation),
+            (String::from(" "), dark.text),
+            (String::from("null"), dark.literal),
+            (String::from("]"), dark.punctuation),
+        ]);
+    }
+
+    #[test]
+    fn highlights_unfinished_strings() {
+        let dark = Theme::dark();
+
+        assert_eq!(tokens(r#""end\"#), [
+            (String::from(r#""end\"#), dark.string),
+        ]);
+        assert_eq!(tokens(r#"{"a"#), [
+            (String::from("{"), dark.punctuation),
+            (String::from(r#""a"#), dark.string),
+        ]);
+    }
+}
@@ -9,29 +9,36 @@ use tui::{
         Constraint,
         Direction,
         Layout,
+        Rect,
     },
     widgets::{
         Block,
         Borders,
+        Clear,
         List,
         ListItem,
         ListState,
         Paragraph,
+        Tabs,
         Wrap,
     },
-    style::{
-        Color,
-        Modifier,
-        Style,
+    text::{
+        Span,
+        Spans,
+        Text,
     },
-    // text::{
-    //     Span,
-    //     Spans,
-    //     Text,
-    // },
 };
 
+use unicode_width::UnicodeWidthChar;
+
 use super::{
+    config::Action,
+    layout::{
+        self,
+        MIN_RESPONSE_HEIGHT,
+        NARROW_WIDTH,
+    },
+    request::ReqField,
     App,
     InputStatus,
     SelElement,
@@ -40,169 +47,434 @@ use super::{
 impl App {
     /// Draw the app UI
     pub fn draw_ui<B: Backend>(&mut self, frame: &mut Frame<B>) {
-        // Holds the keyboard shortcuts span and request elements
+        // Holds the request elements, with the status bar below them
         let main_layout = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
             .constraints([
-                Constraint::Percentage(100),
+                Constraint::Min(0),
+                Constraint::Length(1),
             ].as_ref())
             .split(frame.size());
 
-        // Show keyboard shortcuts (too much screen clutter?)
-        // let (msg, shortcut_style) = match self.input_status {
-        //     InputStatus::NORMAL => (
-        //         vec![
-        //             Span::raw("Use the "),
-        //             Span::styled("arrow keys ", Style::default()
-        //                 .add_modifier(Modifier::BOLD)),
-        //             Span::raw("or "),
-        //             Span::styled("h,j,k,l ", Style::default()
-        //                 .add_modifier(Modifier::BOLD)),
-        //             Span::raw("to navigate. Press "),
-        //             Span::styled("i ", Style::default()
-        //                 .add_modifier(Modifier::BOLD)),
-        //             Span::raw("when hovering over request value to edit it. "),
-        //             Span::raw("Press "),
-        //             Span::styled("Enter ", Style::default()
-        //                 .add_modifier(Modifier::BOLD)),
-        //             Span::raw("to execute selected request. "),
-        //             Span::raw("Press "),
-        //             Span::styled("n ", Style::default()
-        //                 .add_modifier(Modifier::BOLD)),
-        //             Span::raw("to make a new request. "),
-        //             Span::raw("Press "),
-        //             Span::styled("x ", Style::default()
-        //                 .add_modifier(Modifier::BOLD)),
-        //             Span::raw("to delete the selected request."),
-        //         ],
-        //         Style::default()
-        //     ),
-        //     InputStatus::INSERT => (
-        //         vec![
-        //             Span::raw("Press "),
-        //             Span::styled("Enter ", Style::default()
-        //                 .add_modifier(Modifier::BOLD)),
-        //             Span::raw("to update value. Press "),
-        //             Span::styled("Esc ", Style::default()
-        //                 .add_modifier(Modifier::BOLD)),
-        //             Span::raw("to cancel editing."),
-        //         ],
-        //         Style::default().add_modifier(Modifier::RAPID_BLINK)
-        //     )
-        // };
-
-        // let mut shortcut_help = Text::from(Spans::from(msg));
-        // shortcut_help.patch_style(shortcut_style);
-        // let shortcut_text = Paragraph::new(shortcut_help);
-        // frame.render_widget(shortcut_text, main_layout[0]);
-
-        // Holds the request list and request info panel
-        let req_element_layout = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(20),
-                Constraint::Percentage(80),
-            ].as_ref())
-            .split(main_layout[0]);
+        let theme = &self.config.theme;
+
+        // Completions of the command are shown after it
+        if self.input_status == InputStatus::COMMAND {
+            let command = &self.command_line.input;
+            let command_text = Paragraph::new(Spans::from(vec![
+                Span::styled(format!(":{}", command.value()),
+                    theme.text_style()),
+                Span::styled(format!("  {}", self.status_line),
+                    theme.error_style()),
+            ]));
+            frame.render_widget(command_text, main_layout[1]);
+            let col: usize = command.value().chars()
+                .take(command.cursor())
+                .map(|c| c.width().unwrap_or(0))
+                .sum();
+            frame.set_cursor(main_layout[1].x + col as u16 + 1,
+                main_layout[1].y);
+        } else {
+            frame.render_widget(self.status_bar(), main_layout[1]);
+        }
+
+        // Holds the request list and request info panel, or only the
+        // selected one of them on narrow terminals
+        let req_element_layout = if main_layout[0].width < NARROW_WIDTH {
+            match self.sel_element {
+                SelElement::LIST => vec![main_layout[0], Rect::default()],
+                SelElement::INFOPANEL => vec![Rect::default(), main_layout[0]],
+            }
+        } else {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Percentage(self.config.list_width),
+                    Constraint::Percentage(100 - self.config.list_width),
+                ].as_ref())
+                .split(main_layout[0])
+        };
 
         // Render the list of elements to scroll through
+        // The active environment is shown next to the list title
+        let list_title = match self.env_index
+            .and_then(|index| self.environments.get(index)) {
+
+            Some(env) => format!("Requests [{}]", env.name),
+            None => String::from("Requests"),
+        };
         let list_block = Block::default()
-            .title("Requests")
+            .title(list_title)
             .borders(Borders::ALL)
-            .style(Style::default().fg(Color::White));
+            .border_style(theme.border_style());
+
+        // Only the requests that fit are drawn, scrolled to keep the
+        // selected one shown, so clicks can find the request under them
+        let list_area = req_element_layout[0];
+        let list_height = list_area.height.saturating_sub(2).max(1) as usize;
+        let offset = &mut self.areas.list_offset;
+        if self.req_index < *offset {
+            *offset = self.req_index;
+        } else if self.req_index >= *offset + list_height {
+            *offset = self.req_index + 1 - list_height;
+        }
+        let list_offset = *offset;
 
         let mut req_list_state = ListState::default();
-        req_list_state.select(Some(self.req_index));
+        req_list_state.select(self.req_index.checked_sub(list_offset));
 
         let req_list: Vec<_> = self.requests
             .iter()
-            .map(|req| ListItem::new(req.name.clone()))
+            .skip(list_offset)
+            .take(list_height)
+            .map(|req| ListItem::new(Spans::from(vec![
+                Span::styled(format!("{} ", req.req_type),
+                    theme.method_style(&req.req_type)),
+                Span::raw(req.label()),
+            ])))
             .collect();
 
         let req_list_widget = List::new(req_list)
             .block(list_block)
-            .style(Style::default().fg(Color::White))
-            .highlight_style(
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .bg(Color::Yellow)
-            );
+            .style(theme.text_style())
+            .highlight_style(theme.selected_style());
 
-        frame.render_stateful_widget(req_list_widget, req_element_layout[0],
+        frame.render_stateful_widget(req_list_widget, list_area,
             &mut req_list_state);
 
-        // Render the info for the selected request
-        let info_layout = Layout::default()
+        // Results of checking the response, shown between the status and the
+        // response when there are any
+        let check_lines: Vec<String> = self.requests.get(self.req_index)
+            .map(|cur_req| cur_req.checks.iter()
+                .flat_map(|check| {
+                    let result = if check.violations.is_empty() {
+                        "PASS"
+                    } else {
+                        "FAIL"
+                    };
+
+                    std::iter::once(format!("{} {}", result, check.name))
+                        .chain(check.violations.iter()
+                            .map(|violation| format!("  {}", violation)))
+                })
+                .collect())
+            .unwrap_or_default();
+        let checks_height = if check_lines.is_empty() {
+            0
+        } else {
+            (check_lines.len() as u16 + 2).min(10)
+        };
+
+        // Tabs are shown above the info panel when there are several
+        let mut info_area = req_element_layout[1];
+        if self.tabs.tabs.len() > 1 && info_area.height > 0 {
+            let titles: Vec<Spans> = self.tab_requests().iter()
+                .map(|index| Spans::from(self.requests.get(*index)
+                    .map_or(String::new(), |req| req.name.clone())))
+                .collect();
+            let tab_bar = Tabs::new(titles)
+                .select(self.tabs.active)
+                .style(theme.text_style())
+                .highlight_style(theme.selected_style());
+
+            frame.render_widget(tab_bar, Rect {
+                height: 1,
+                ..info_area
+            });
+            info_area.y += 1;
+            info_area.height -= 1;
+        }
+
+        // Render the info for the selected request, giving fields with
+        // multiple lines more room. Fields that don't fit on short terminals
+        // aren't shown, so the selected one always is
+        let multiline: Vec<bool> = self.fields.iter()
+            .map(|field| field.is_multiline())
+            .collect();
+        let heights = layout::field_heights(&multiline, self.input_index,
+            info_area.height, 3 + checks_height + MIN_RESPONSE_HEIGHT,
+            self.config.multiline_height);
+
+        let mut info_constraints: Vec<_> = heights.iter()
+            .filter(|height| **height > 0)
+            .map(|height| Constraint::Length(*height))
+            .collect();
+        info_constraints.push(Constraint::Length(3));
+        if checks_height > 0 {
+            info_constraints.push(Constraint::Length(checks_height));
+        }
+        info_constraints.push(Constraint::Min(0));
+
+        let shown_layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Percentage(10),
-                Constraint::Percentage(10),
-                Constraint::Percentage(10),
-                Constraint::Percentage(20),
-                Constraint::Percentage(10),
-                Constraint::Percentage(40),
-            ].as_ref())
-            .split(req_element_layout[1]);
+            .constraints(info_constraints)
+            .split(info_area);
+
+        // Hidden fields get empty areas, so the layout still has one area
+        // for each field
+        let mut shown_areas = shown_layout.iter();
+        let mut info_layout: Vec<Rect> = heights.iter()
+            .map(|height| if *height > 0 {
+                *shown_areas.next().unwrap()
+            } else {
+                Rect::default()
+            })
+            .collect();
+        info_layout.extend(shown_areas);
+
+        // Remember where things are drawn for mouse clicks
+        self.areas.main = main_layout[0];
+        self.areas.list = list_area;
+        self.areas.fields = info_layout[..self.fields.len()].to_vec();
+        self.areas.response = info_layout[info_layout.len() - 1];
 
-        // Render input boxes
-        let norm_style = Style::default().fg(Color::White);
-        let sel_style = Style::default().fg(Color::White).bg(Color::Yellow);
+        // Field titles depend on the current request's body mode
+        let body_mode = self.requests.get(self.req_index)
+            .map_or(Default::default(), |cur_req| cur_req.body_mode);
+
+        // Render input boxes, with the method in its colour
+        let norm_style = theme.text_style();
+        let sel_style = theme.selected_style();
         for i in 0..self.inputs.len() {
+            let mut title = self.fields[i].title(body_mode).to_string();
+            if i == self.input_index && !self.completions.is_empty() {
+                title = format!("{} ({})", title, self.completions.join(" | "));
+            }
+
             let input_box = Paragraph::new(self.inputs[i].value())
-                .block(Block::default().title(
-                    match i {
-                        0 => "Name",
-                        1 => "Request Type",
-                        2 => "Url",
-                        3 => "Body",
-                        _ => "",
-                    }
-                ).borders(Borders::ALL))
+                .block(Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style()))
                 .style(
                     if (i == self.input_index) &&
                     (self.sel_element == SelElement::INFOPANEL) {
                         sel_style
+                    } else if self.fields[i] == ReqField::Method {
+                        theme.method_style(self.inputs[i].value())
                     } else {
                         norm_style
                     }
                 )
                 .wrap(Wrap {trim: false});
 
-            frame.render_widget(input_box, info_layout[i]);
+            if info_layout[i].area() > 0 {
+                frame.render_widget(input_box, info_layout[i]);
+            }
         }
 
-        // Render cursor
+        // Render cursor where it is in the wrapped text, kept inside the
+        // field's borders
         if self.input_status == InputStatus::INSERT {
             let sel_layout = &info_layout[self.input_index];
             let sel_input = &self.inputs[self.input_index];
+            let width = sel_layout.width.saturating_sub(2).max(1);
+            let height = sel_layout.height.saturating_sub(2).max(1);
+
+            let (col, line) = layout::cursor_position(sel_input.value(),
+                sel_input.cursor(), width);
 
             frame.set_cursor(
-                sel_layout.x + (sel_input.cursor() as u16) + 1,
-                sel_layout.y + (sel_input.value().len() as u16 / sel_layout.width) + 1
+                sel_layout.x + col.min(width - 1) + 1,
+                sel_layout.y + line.min(height - 1) + 1
             );
         }
 
-        // Render response
+        // Render response, colouring JSON bodies
         let (response_text, status_text) = match self.requests
             .get(self.req_index) {
 
-            Some(cur_req) => (cur_req.resp.clone(), cur_req.status.clone()),
-            None => (String::new(), String::new()),
+            // WebSocket requests show their session log, newest frame first
+            Some(cur_req) if cur_req.is_websocket() => (
+                Text::from(cur_req.ws_session.as_ref()
+                    .map_or(String::new(), |session| session.log.iter().rev()
+                        .map(|entry| entry.to_line())
+                        .collect::<Vec<_>>()
+                        .join("\n"))),
+                cur_req.status.clone(),
+            ),
+            // Streamed events are shown newest first too
+            Some(cur_req) if cur_req.sse_stream.is_some() => (
+                Text::from(cur_req.sse_stream.as_ref()
+                    .map_or(String::new(), |stream| stream.log.iter().rev()
+                        .map(|event| event.to_lines())
+                        .collect::<Vec<_>>()
+                        .join("\n\n"))),
+                cur_req.status.clone(),
+            ),
+            Some(cur_req) if !cur_req.notice.is_empty() => (
+                theme.highlight(cur_req.response_text()),
+                format!("{}  {}", cur_req.status, cur_req.notice),
+            ),
+            Some(cur_req) => (theme.highlight(cur_req.response_text()),
+                cur_req.status.clone()),
+            None => (Text::default(), String::new()),
+        };
+
+        // Summarize the checks of the response next to its status
+        let checks_summary: Vec<String> = self.requests.get(self.req_index)
+            .map(|cur_req| cur_req.checks.iter()
+                .map(|check| match check.violations.len() {
+                    0 => format!("[{}: pass]", check.name),
+                    1 => format!("[{}: 1 error]", check.name),
+                    count => format!("[{}: {} errors]", check.name, count),
+                })
+                .collect())
+            .unwrap_or_default();
+        let status_text = if checks_summary.is_empty() {
+            status_text
+        } else {
+            format!("{}  {}", status_text, checks_summary.join(" "))
         };
 
+        // The status is coloured by the class of its code
+        let status_style = theme.status_style(&status_text);
         let response_code_box = Paragraph::new(status_text)
-            .block(Block::default().title("Status Code").borders(Borders::ALL))
-            .style(norm_style);
+            .block(Block::default()
+                .title("Status Code")
+                .borders(Borders::ALL)
+                .border_style(theme.border_style()))
+            .style(status_style);
         frame.render_widget(
-            response_code_box, info_layout[4]
+            response_code_box, info_layout[self.fields.len()]
         );
 
+        if !check_lines.is_empty() {
+            let checks_box = Paragraph::new(check_lines.join("\n"))
+                .block(Block::default()
+                    .title("Validation")
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style()))
+                .style(norm_style);
+            frame.render_widget(checks_box, info_layout[self.fields.len() + 1]);
+        }
+
         let response_text_box = Paragraph::new(response_text)
-            .block(Block::default().title("Response").borders(Borders::ALL))
+            .block(Block::default()
+                .title("Response")
+                .borders(Borders::ALL)
+                .border_style(theme.border_style()))
             .style(norm_style);
         frame.render_widget(
-            response_text_box.scroll(self.text_offset), info_layout[5]
+            response_text_box.scroll(self.text_offset),
+            info_layout[info_layout.len() - 1]
         );
+
+        self.draw_picker(frame);
+        self.draw_help(frame);
+    }
+
+    /// The status bar, showing the mode, the active environment, and the
+    /// status line's message or how to get help
+    fn status_bar(&self) -> Paragraph<'static> {
+        let theme = &self.config.theme;
+
+        let mode = match self.input_status {
+            InputStatus::NORMAL => " NORMAL ",
+            InputStatus::INSERT => " INSERT ",
+            InputStatus::COMMAND => " COMMAND ",
+        };
+        let env = match self.env_index
+            .and_then(|index| self.environments.get(index)) {
+
+            Some(env) => format!(" {} ", env.name),
+            None => String::from(" no environment "),
+        };
+        // Keys of a binding with several keys are shown until it's finished
+        let message = if !self.pending_keys.is_empty() {
+            let keys: Vec<String> = self.pending_keys.iter()
+                .map(|key| key.to_string())
+                .collect();
+            Span::styled(format!(" {}", keys.join(" ")), theme.text_style())
+        } else if self.status_line.is_empty() {
+            let help_key = self.config.keys.keys(Action::Help).first()
+                .map_or(String::new(), |key| key.to_string());
+            Span::styled(format!(" Press {} for help", help_key),
+                theme.border_style())
+        } else {
+            Span::styled(format!(" {}", self.status_line),
+                theme.error_style())
+        };
+
+        Paragraph::new(Spans::from(vec![
+            Span::styled(mode, theme.selected_style()),
+            Span::styled(env, theme.text_style()),
+            message,
+        ]))
+    }
+
+    /// Draw the keys for the current mode and pane over the rest of the UI,
+    /// if the help is open
+    fn draw_help<B: Backend>(&self, frame: &mut Frame<B>) {
+        if !self.show_help {
+            return;
+        }
+
+        let entries = self.help_entries();
+        let keys_width = entries.iter()
+            .map(|(keys, _)| keys.chars().count())
+            .max()
+            .unwrap_or(0);
+        let lines: Vec<Spans> = entries.iter()
+            .map(|(keys, description)| Spans::from(vec![
+                Span::styled(format!("{:width$}  ", keys, width = keys_width),
+                    self.config.theme.selected_style()),
+                Span::styled(description.as_str(),
+                    self.config.theme.text_style()),
+            ]))
+            .collect();
+
+        let size = frame.size();
+        let width = (lines.iter().map(|line| line.width()).max()
+            .unwrap_or(0) as u16 + 4)
+            .min(size.width);
+        let height = (lines.len() as u16 + 2).min(size.height);
+        let area = Rect::new((size.width - width) / 2,
+            (size.height - height) / 2, width, height);
+
+        let help = Paragraph::new(lines)
+            .block(Block::default()
+                .title(self.help_title())
+                .borders(Borders::ALL)
+                .border_style(self.config.theme.border_style()));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(help, area);
+    }
+
+    /// Draw the open picker, if any, in the middle of the screen over the rest
+    /// of the UI
+    fn draw_picker<B: Backend>(&self, frame: &mut Frame<B>) {
+        let picker = match &self.picker {
+            Some(picker) => picker,
+            None => return,
+        };
+
+        let size = frame.size();
+        let width = (picker.title.chars().count() as u16 + 4)
+            .max(30)
+            .min(size.width);
+        let height = (picker.items.len() as u16 + 2).min(size.height);
+        let area = Rect::new((size.width - width) / 2,
+            (size.height - height) / 2, width, height);
+
+        let mut picker_state = ListState::default();
+        picker_state.select(Some(picker.index));
+
+        let items: Vec<_> = picker.items.iter()
+            .map(|item| ListItem::new(item.as_str()))
+            .collect();
+        let theme = &self.config.theme;
+        let picker_widget = List::new(items)
+            .block(Block::default()
+                .title(picker.title.as_str())
+                .borders(Borders::ALL)
+                .border_style(theme.border_style()))
+            .style(theme.text_style())
+            .highlight_style(theme.selected_style());
+
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(picker_widget, area, &mut picker_state);
     }
 }
@@ -0,0 +1,188 @@
+//! # validation
+//!
+//! `validation` implements methods to check finished responses against the
+//! OpenAPI operations their requests are linked to, and the JSON Schemas
+//! attached to them.
+
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use serde_json::Value;
+
+use super::{
+    download::PREVIEW_LEN,
+    openapi,
+    schema::{
+        self,
+        Violation,
+    },
+    App,
+};
+
+/// A spec loaded for validating responses
+pub struct Spec {
+    pub path: PathBuf,
+    pub doc: Value,
+}
+
+/// The result of checking a response against one source of rules
+pub struct CheckResult {
+    // What the response was checked against, like `GET /pets/{id}`
+    pub name: String,
+    pub violations: Vec<Violation>,
+}
+
+impl App {
+    /// Load a spec to validate the responses of requests matching its
+    /// operations
+    pub fn load_spec(&mut self, path: &Path)
+        -> Result<(), Box<dyn std::error::Error>> {
+
+        let path = fs::canonicalize(path)?;
+        if self.specs.iter().any(|spec| spec.path == path) {
+            return Ok(());
+        }
+
+        let doc = openapi::load_document(&path)?;
+        if !openapi::is_spec(&doc) {
+            return Err(format!("{} isn't an OpenAPI or Swagger spec",
+                path.display()).into());
+        }
+
+        self.specs.push(Spec { path, doc });
+        Ok(())
+    }
+
+    /// Check the finished responses of requests that haven't been checked yet
+    pub fn check_responses(&mut self) {
+        for index in 0..self.requests.len() {
+            let cur_req = &self.requests[index];
+            let is_done = cur_req.download.as_ref()
+                .is_some_and(|download| download.is_done);
+
+            if cur_req.checks_pending && is_done {
+                self.requests[index].checks_pending = false;
+                self.check_response(index);
+            }
+        }
+    }
+
+    /// Check the response of the request at an index in the request list
+    fn check_response(&mut self, index: usize) {
+        self.check_openapi(index);
+        self.check_json_schema(index);
+    }
+
+    /// Validate the response against the OpenAPI operation the request
+    /// matches, if any
+    fn check_openapi(&mut self, index: usize) {
+        let variables = self.request_variables(index);
+        let cur_req = &self.requests[index];
+
+        // Load the spec the request was linked to in an earlier session
+        if !cur_req.spec_path.is_empty() {
+            let spec_path = PathBuf::from(&cur_req.spec_path);
+            if let Err(e) = self.load_spec(&spec_path) {
+                self.requests[index].notice = format!("{}", e);
+            }
+        }
+
+        let cur_req = &self.requests[index];
+        let url_path = reqwest::Url::parse(&cur_req.resolved_url(&variables))
+            .map(|url| url.path().to_string())
+            .unwrap_or_default();
+
+        // Check the linked spec first, then any other loaded spec
+        let linked = self.specs.iter()
+            .position(|spec| spec.path == Path::new(&cur_req.spec_path));
+        let order = linked.into_iter()
+            .chain((0..self.specs.len()).filter(|i| Some(*i) != linked));
+
+        let mut matched = None;
+        for spec_index in order {
+            let doc = &self.specs[spec_index].doc;
+            if let Some((template, operation)) = openapi::find_operation(doc,
+                &cur_req.req_type, &url_path) {
+
+                matched = Some((spec_index, template.to_string(), operation));
+                break;
+            }
+        }
+
+        let (spec_index, template, operation) = match matched {
+            Some(matched) => matched,
+            None => return,
+        };
+
+        let body = self.response_body(index);
+        let cur_req = &self.requests[index];
+        let spec = &self.specs[spec_index];
+        let violations = openapi::validate_response(&spec.doc, operation,
+            &cur_req.status, &cur_req.resp_headers, body.as_deref());
+        let spec_path = spec.path.display().to_string();
+
+        let cur_req = &mut self.requests[index];
+        cur_req.checks.push(CheckResult {
+            name: format!("{} {}", cur_req.req_type, template),
+            violations,
+        });
+
+        // Remember the link so the spec is loaded for this request next time
+        if cur_req.spec_path != spec_path {
+            cur_req.spec_path = spec_path;
+            self.save_request(index);
+        }
+    }
+
+    /// Validate the response body against the JSON Schema attached to the
+    /// request, if it has one
+    fn check_json_schema(&mut self, index: usize) {
+        if self.requests[index].response_schema.is_empty() {
+            return;
+        }
+
+        let body = self.response_body(index);
+        let cur_req = &mut self.requests[index];
+
+        let violations = match schema::load(&cur_req.response_schema) {
+            Ok(json_schema) => match body.as_deref()
+                .map(serde_json::from_slice::<Value>) {
+
+                Some(Ok(body)) =>
+                    schema::validate(&json_schema, &json_schema, &body),
+                Some(Err(e)) => vec![Violation {
+                    pointer: String::from("body"),
+                    message: format!("Isn't valid JSON: {}", e),
+                }],
+                None => vec![openapi::body_too_big()],
+            },
+            Err(e) => vec![Violation {
+                pointer: String::from("schema"),
+                message: format!("Couldn't load the schema: {}", e),
+            }],
+        };
+
+        cur_req.checks.push(CheckResult {
+            name: String::from("JSON Schema"),
+            violations,
+        });
+    }
+
+    /// The full body of the last response of the request at an index, if
+    /// it's small enough to be kept in memory
+    ///
+    /// Only the first `PREVIEW_LEN` bytes of a body are kept, so bigger ones
+    /// aren't read back from their file
+    fn response_body(&self, index: usize) -> Option<Vec<u8>> {
+        match &self.requests[index].download {
+            Some(download) if download.read > PREVIEW_LEN as u64 => None,
+            Some(download) => Some(download.preview.clone()),
+            None => Some(Vec::new()),
+        }
+    }
+}
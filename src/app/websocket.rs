@@ -0,0 +1,229 @@
+//! # websocket
+//!
+//! The `websocket` crate contains structures and methods for WebSocket client
+//! sessions, which run on their own thread so the UI stays responsive.
+
+use std::{
+    io,
+    net::TcpStream,
+    sync::mpsc,
+    thread,
+    time::{
+        Duration,
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use tungstenite::{
+    client::IntoClientRequest,
+    http::header::{
+        HeaderName,
+        HeaderValue,
+    },
+    stream::MaybeTlsStream,
+    Message,
+    WebSocket,
+};
+
+/// Value of `Request::req_type` for WebSocket requests
+pub const WEBSOCKET_TYPE: &str = "WS";
+
+// How long the session thread waits for a frame before checking for frames
+// to send, in milliseconds
+const READ_TIMEOUT: u64 = 50;
+
+/// Which way a logged frame went
+#[derive(PartialEq)]
+pub enum Direction {
+    Sent,
+    Received,
+    // Connection events, like opening, closing and errors
+    Info,
+}
+
+/// A frame or connection event in a session's log
+pub struct LogEntry {
+    // Time of the entry as HH:MM:SS.mmm (UTC)
+    pub time: String,
+    pub direction: Direction,
+    pub text: String,
+}
+
+impl LogEntry {
+    fn new(direction: Direction, text: String) -> Self {
+        Self { time: timestamp(), direction, text }
+    }
+
+    /// Format the entry as a line in the log view
+    pub fn to_line(&self) -> String {
+        let arrow = match self.direction {
+            Direction::Sent => "->",
+            Direction::Received => "<-",
+            Direction::Info => "--",
+        };
+
+        format!("{} {} {}", self.time, arrow, self.text)
+    }
+}
+
+/// Messages from the UI to the session thread
+enum Command {
+    Send(String),
+    Close,
+}
+
+/// Messages from the session thread to the UI
+enum SessionEvent {
+    Opened,
+    Frame(LogEntry),
+    Closed(String),
+}
+
+/// An open (or opening) connection to a WebSocket server
+pub struct WsSession {
+    commands: mpsc::Sender<Command>,
+    events: mpsc::Receiver<SessionEvent>,
+    pub log: Vec<LogEntry>,
+    pub is_open: bool,
+}
+
+impl WsSession {
+    /// Start connecting to a `ws://` or `wss://` url on a new thread
+    ///
+    /// Takes in the url and the headers sent with the opening handshake
+    pub fn connect(url: &str, headers: Vec<(String, String)>) -> Self {
+        let (commands, command_receiver) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+        let thread_url = url.to_string();
+
+        thread::spawn(move || {
+            let reason = match run(&thread_url, headers, &command_receiver,
+                &event_sender) {
+
+                Ok(()) => String::from("Connection closed"),
+                Err(e) => format!("{}", e),
+            };
+            event_sender.send(SessionEvent::Closed(reason)).ok();
+        });
+
+        Self {
+            commands,
+            events,
+            log: vec![LogEntry::new(Direction::Info,
+                format!("Connecting to {}", url))],
+            is_open: false,
+        }
+    }
+
+    /// Send a text frame
+    pub fn send(&self, text: String) {
+        self.commands.send(Command::Send(text)).ok();
+    }
+
+    /// Close the connection
+    pub fn close(&self) {
+        self.commands.send(Command::Close).ok();
+    }
+
+    /// Move frames from the session thread into the log
+    ///
+    /// Returns whether anything new was logged
+    pub fn update(&mut self) -> bool {
+        let mut updated = false;
+
+        while let Ok(event) = self.events.try_recv() {
+            updated = true;
+            match event {
+                SessionEvent::Opened => {
+                    self.is_open = true;
+                    self.log.push(LogEntry::new(Direction::Info,
+                        String::from("Connected")));
+                },
+                SessionEvent::Frame(entry) => self.log.push(entry),
+                SessionEvent::Closed(reason) => {
+                    self.is_open = false;
+                    self.log.push(LogEntry::new(Direction::Info, reason));
+                },
+            }
+        }
+
+        updated
+    }
+}
+
+impl Drop for WsSession {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Connect and pass frames between the server and the UI until either side
+/// closes the connection
+fn run(url: &str, headers: Vec<(String, String)>,
+    commands: &mpsc::Receiver<Command>, events: &mpsc::Sender<SessionEvent>)
+    -> Result<(), Box<dyn std::error::Error>> {
+
+    let mut request = url.into_client_request()?;
+    for (name, value) in headers {
+        request.headers_mut().insert(HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(&value)?);
+    }
+
+    let (mut socket, _) = tungstenite::connect(request)?;
+    set_read_timeout(&mut socket)?;
+    events.send(SessionEvent::Opened)?;
+
+    loop {
+        // Send any frames composed in the UI
+        while let Ok(command) = commands.try_recv() {
+            match command {
+                Command::Send(text) => {
+                    socket.send(Message::Text(text.clone()))?;
+                    events.send(SessionEvent::Frame(
+                        LogEntry::new(Direction::Sent, text)))?;
+                },
+                Command::Close => socket.close(None)?,
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => events.send(SessionEvent::Frame(
+                LogEntry::new(Direction::Received, text)))?,
+            Ok(Message::Binary(data)) => events.send(SessionEvent::Frame(
+                LogEntry::new(Direction::Received,
+                    format!("<binary frame, {} bytes>", data.len()))))?,
+            Ok(_) => (),
+            Err(tungstenite::Error::Io(e))
+                if e.kind() == io::ErrorKind::WouldBlock
+                || e.kind() == io::ErrorKind::TimedOut => (),
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Make reads on the socket time out so the session thread can also check
+/// for frames to send
+fn set_read_timeout(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>)
+    -> io::Result<()> {
+
+    let timeout = Some(Duration::from_millis(READ_TIMEOUT));
+
+    match socket.get_mut() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(timeout),
+        MaybeTlsStream::NativeTls(stream) =>
+            stream.get_mut().set_read_timeout(timeout),
+        _ => Ok(()),
+    }
+}
+
+/// Get the current time of day as HH:MM:SS.mmm (UTC)
+fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs() % 86400;
+
+    format!("{:02}:{:02}:{:02}.{:03}", secs / 3600, (secs / 60) % 60,
+        secs % 60, now.subsec_millis())
+}
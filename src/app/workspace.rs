@@ -0,0 +1,58 @@
+//! # workspace
+//!
+//! The `workspace` crate finds the directory requests and environments are
+//! stored in. A `.almagro/` directory in the current directory or one of its
+//! ancestors is used like git finds its repository, so projects can carry
+//! their own requests, and `~/.almagro` is used otherwise.
+
+use std::{
+    env,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// Name of the directory holding a workspace's data
+pub const WORKSPACE_DIR: &str = ".almagro";
+
+/// Find the workspace directory to use, creating it if it doesn't exist
+///
+/// Takes in a directory given on the command line, which is used instead of
+/// searching if there is one
+pub fn find(dir: Option<&Path>) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let workspace = match dir {
+        Some(dir) => workspace_in(dir),
+        None => discover(&env::current_dir()?).map_or_else(global, Ok)?,
+    };
+
+    fs::create_dir_all(&workspace)?;
+    Ok(workspace)
+}
+
+/// The workspace directory for a project directory, or the directory itself
+/// if it's already a workspace directory
+fn workspace_in(dir: &Path) -> PathBuf {
+    if dir.file_name().is_some_and(|name| name == WORKSPACE_DIR) {
+        dir.to_path_buf()
+    } else {
+        dir.join(WORKSPACE_DIR)
+    }
+}
+
+/// Find the closest workspace directory in a directory or its ancestors
+fn discover(start: &Path) -> Option<PathBuf> {
+    start.ancestors()
+        .map(|dir| dir.join(WORKSPACE_DIR))
+        .find(|workspace| workspace.is_dir())
+}
+
+/// The global workspace directory in the user's home directory, which also
+/// holds the config file
+pub fn global() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home_dir = home::home_dir()
+        .ok_or("Couldn't get user home directory")?;
+
+    Ok(home_dir.join(WORKSPACE_DIR))
+}
@@ -1,13 +1,23 @@
 //! # event
 //!
-//! Contains methods to handle input events
+//! Contains methods to handle input events from the keyboard and mouse, and
+//! terminal resizes
 
 use crossterm::event::{
     self,
     Event,
+    MouseEvent,
+    MouseEventKind,
 };
 use std::{
-    sync::mpsc,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        mpsc,
+        Arc,
+    },
     time::{
         Duration,
         Instant,
@@ -21,37 +31,66 @@ use std::{
 //     Key(KeyEvent),
 // }
 
+/// Longest the thread waits for an event before checking if it's paused
+const PAUSE_CHECK: Duration = Duration::from_millis(50);
+
 pub struct EventHandler {
     // Channel to send events
     _sender: mpsc::Sender<Option<Event>>,
     // Channel to receive events
     receiver: mpsc::Receiver<Option<Event>>,
+    // Whether the thread stops reading events, while another program uses
+    // the terminal, and the channel it says it has stopped on
+    paused: Arc<AtomicBool>,
+    stopped: mpsc::Receiver<()>,
     // Thread to handle events
     _handler: thread::JoinHandle<()>,
 }
 
-// Delay for reading events in milliseconds
-const DELAY: u64 = 250;
-
 impl EventHandler {
     /// Constructs a new EventHandler, and the thread channel to send/receive
     /// events
-    pub fn new() -> Self {
+    ///
+    /// Takes in the delay between ticks when no events occur
+    pub fn new(tick_len: Duration) -> Self {
         let (sender, receiver) = mpsc::channel();
-        let tick_len = Duration::from_millis(DELAY);
+        let (stopped_sender, stopped) = mpsc::channel();
+        let paused = Arc::new(AtomicBool::new(false));
         let handler = {
             let sender = sender.clone();
+            let paused = paused.clone();
             thread::spawn(move || {
                 let mut last_tick = Instant::now();
+                let mut said_stopped = false;
                 loop {
+                    if paused.load(Ordering::SeqCst) {
+                        if !said_stopped {
+                            stopped_sender.send(())
+                                .expect("Couldn't send pause event");
+                            said_stopped = true;
+                        }
+                        thread::sleep(PAUSE_CHECK);
+                        continue;
+                    }
+                    said_stopped = false;
+
                     let timeout = tick_len
                         .checked_sub(last_tick.elapsed())
-                        .unwrap_or(tick_len);
+                        .unwrap_or(tick_len)
+                        .min(PAUSE_CHECK);
 
                     if event::poll(timeout).expect("No events available") {
                         match event::read().expect("Couldn't read event") {
                             event::Event::Key(e) => sender.send(Some(Event::Key(e))),
-                            _ => Ok(())
+                            // Moving the mouse without a button held
+                            // doesn't do anything
+                            event::Event::Mouse(MouseEvent {
+                                kind: MouseEventKind::Moved,
+                                ..
+                            }) => Ok(()),
+                            // Other mouse events, and resizes so the UI is
+                            // drawn again for the new size
+                            e => sender.send(Some(e)),
                         }.expect("Failed to send event")
                     }
 
@@ -63,7 +102,28 @@ impl EventHandler {
                 }
             })
         };
-        Self { _sender: sender, receiver, _handler: handler }
+        Self {
+            _sender: sender,
+            receiver,
+            paused,
+            stopped,
+            _handler: handler,
+        }
+    }
+
+    /// Stop reading events, so another program like an editor can read the
+    /// terminal, until `resume` is called
+    ///
+    /// Waits for the thread to finish waiting for an event
+    pub fn pause(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.paused.store(true, Ordering::SeqCst);
+        self.stopped.recv()?;
+        Ok(())
+    }
+
+    /// Read events again after `pause`
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
     }
 
     /// Check the next event from the handler thread
@@ -1,4 +1,8 @@
-use std::io;
+use std::{
+    env,
+    io,
+    path::PathBuf,
+};
 use tui::{
     backend::CrosstermBackend,
     terminal::Terminal,
@@ -15,16 +19,58 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let backend = CrosstermBackend::new(io::stdout());
     let terminal = Terminal::new(backend)?;
     let mut tui = Tui::new(terminal);
-    let handler = EventHandler::new();
-    let mut app = App::new()?;
+
+    // Files given with `--import <path>` are imported, `.http` files given
+    // with `--open <path>` are opened, and specs given with `--spec <path>`
+    // are loaded to validate responses against, before starting the UI.
+    // `--workspace <path>` picks the directory requests are stored in
+    let mut workspace = None;
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--import" | "--open" | "--spec" | "--workspace" => {
+                let path = PathBuf::from(args.next()
+                    .ok_or(format!("{} needs a file path", arg))?);
+
+                if arg == "--workspace" {
+                    workspace = Some(path);
+                } else {
+                    files.push((arg, path));
+                }
+            },
+            _ => return Err(format!("Unknown argument {}", arg).into()),
+        }
+    }
+
+    let mut app = App::new(workspace.as_deref())?;
+    for (arg, path) in files {
+        match arg.as_str() {
+            "--import" => { app.import_file(&path)?; },
+            "--open" => { app.open_http_file(&path)?; },
+            _ => app.load_spec(&path)?,
+        }
+    }
+
+    let handler = EventHandler::new(app.config.tick_delay);
 
     tui.init()?;
     while app.is_running {
+        app.update();
         tui.draw(&mut app)?;
 
         if let Some(e) = handler.next()? {
             app.handle_keys(e);
         }
+
+        // The editor has the terminal until it's closed
+        if let Some(target) = app.edit_target.take() {
+            handler.pause()?;
+            tui.exit()?;
+            app.edit(target);
+            tui.init()?;
+            handler.resume();
+        }
     }
 
     tui.exit()?;
@@ -3,10 +3,16 @@
 //! Contains the methods to initialize and destruct the terminal interface used
 //! to render the app's UI, and provides a method to draw the app's UI
 
-use crossterm::terminal::{
-    self,
-    EnterAlternateScreen,
-    LeaveAlternateScreen,
+use crossterm::{
+    event::{
+        DisableMouseCapture,
+        EnableMouseCapture,
+    },
+    terminal::{
+        self,
+        EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
 use std::io;
 use tui::{
@@ -34,7 +40,8 @@ impl<B: Backend> Tui<B> {
     /// Prepares the Tui's Terminal object for displaying the UI
     pub fn init(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         terminal::enable_raw_mode()?;
-        crossterm::execute!(io::stderr(), EnterAlternateScreen)?;
+        crossterm::execute!(io::stderr(), EnterAlternateScreen,
+            EnableMouseCapture)?;
         self.terminal.hide_cursor()?;
         self.terminal.clear()?;
 
@@ -44,7 +51,8 @@ impl<B: Backend> Tui<B> {
     /// Clean up the terminal on application exit
     pub fn exit(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         terminal::disable_raw_mode()?;
-        crossterm::execute!(io::stderr(), LeaveAlternateScreen)?;
+        crossterm::execute!(io::stderr(), LeaveAlternateScreen,
+            DisableMouseCapture)?;
         self.terminal.show_cursor()?;
 
         Ok(())
@@ -0,0 +1,198 @@
+//! Upgrading workspaces saved by older versions, using the fixture workspaces
+//! in `tests/fixtures`, one for each schema version
+
+use std::{
+    collections::BTreeMap,
+    env,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    process,
+};
+
+use almagro::app::{
+    migration::{
+        self,
+        SCHEMA_VERSION,
+        VERSION_FILE,
+    },
+    request::{
+        BodyMode,
+        Request,
+    },
+};
+
+/// Copy a fixture workspace to a new temporary directory for a test
+fn workspace_from(fixture: &str, test: &str) -> PathBuf {
+    let workspace = env::temp_dir()
+        .join(format!("almagro-test-{}-{}", process::id(), test));
+    fs::remove_dir_all(&workspace).ok();
+    fs::create_dir_all(&workspace).unwrap();
+
+    let fixture_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(fixture);
+    if fixture_dir.is_dir() {
+        for entry in fs::read_dir(fixture_dir).unwrap() {
+            let path = entry.unwrap().path();
+            fs::copy(&path, workspace.join(path.file_name().unwrap())).unwrap();
+        }
+    }
+
+    workspace
+}
+
+/// Load the requests in a workspace's database
+fn requests(workspace: &Path) -> BTreeMap<String, Request> {
+    let text = fs::read_to_string(workspace.join("data.json")).unwrap();
+    serde_json::from_str(&text).unwrap()
+}
+
+fn version(workspace: &Path) -> String {
+    fs::read_to_string(workspace.join(VERSION_FILE)).unwrap()
+}
+
+#[test]
+fn upgrades_version_1() {
+    let workspace = workspace_from("v1", "upgrades_version_1");
+    let original = fs::read_to_string(workspace.join("data.json")).unwrap();
+
+    assert_eq!(migration::migrate(&workspace).unwrap(), 1);
+
+    // The original database is backed up as it was
+    let backup = fs::read_to_string(workspace.join("data.v1.json.bak"))
+        .unwrap();
+    assert_eq!(backup, original);
+    assert_eq!(version(&workspace).trim(), SCHEMA_VERSION.to_string());
+
+    let requests = requests(&workspace);
+    assert_eq!(requests.len(), 2);
+
+    let create = requests.values()
+        .find(|req| req.name == "Create user")
+        .unwrap();
+    assert_eq!(create.req_type, "POST");
+    assert_eq!(create.body, "{\"name\":\"Ada\"}");
+    assert!(create.body_mode == BodyMode::Raw);
+    assert_eq!(create.headers, "");
+    assert_eq!(create.folder, "");
+
+    // Every field of the current layout is written out
+    let data: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(workspace.join("data.json")).unwrap()).unwrap();
+    for req in data.as_object().unwrap().values() {
+        for field in ["headers", "body_mode", "content_type", "graphql"] {
+            assert!(req.get(field).is_some(), "{} is missing", field);
+        }
+    }
+
+    fs::remove_dir_all(workspace).ok();
+}
+
+#[test]
+fn leaves_current_version_alone() {
+    let workspace = workspace_from("v2", "leaves_current_version_alone");
+    let original = fs::read_to_string(workspace.join("data.json")).unwrap();
+
+    assert_eq!(migration::migrate(&workspace).unwrap(), 2);
+
+    assert_eq!(fs::read_to_string(workspace.join("data.json")).unwrap(),
+        original);
+    assert!(!workspace.join("data.v2.json.bak").exists());
+
+    let requests = requests(&workspace);
+    let login = requests.values().next().unwrap();
+    assert_eq!(login.folder, "Auth");
+    assert!(login.body_mode == BodyMode::FormUrlEncoded);
+    assert_eq!(login.form[0].key, "user");
+
+    fs::remove_dir_all(workspace).ok();
+}
+
+#[test]
+fn marks_new_workspaces_as_current() {
+    let workspace = workspace_from("new", "marks_new_workspaces_as_current");
+
+    assert_eq!(migration::migrate(&workspace).unwrap(), SCHEMA_VERSION);
+    assert_eq!(version(&workspace).trim(), SCHEMA_VERSION.to_string());
+
+    fs::remove_dir_all(workspace).ok();
+}
+
+#[test]
+fn refuses_newer_versions() {
+    let workspace = workspace_from("v2", "refuses_newer_versions");
+    fs::write(workspace.join(VERSION_FILE), format!("{}\n",
+        SCHEMA_VERSION + 1)).unwrap();
+    let original = fs::read_to_string(workspace.join("data.json")).unwrap();
+
+    assert!(migration::migrate(&workspace).is_err());
+    assert_eq!(fs::read_to_string(workspace.join("data.json")).unwrap(),
+        original);
+
+    fs::remove_dir_all(workspace).ok();
+}
+
+#[test]
+fn keeps_the_first_backup_when_retried() {
+    let workspace = workspace_from("v1", "keeps_the_first_backup_when_retried");
+    let original = fs::read_to_string(workspace.join("data.json")).unwrap();
+    fs::write(workspace.join("environments.json"), "{").unwrap();
+
+    // The database is upgraded before the broken environments stop the
+    // migration
+    assert!(migration::migrate(&workspace).is_err());
+    assert!(!workspace.join(VERSION_FILE).exists());
+    assert_ne!(fs::read_to_string(workspace.join("data.json")).unwrap(),
+        original);
+
+    fs::write(workspace.join("environments.json"), "{}").unwrap();
+    assert_eq!(migration::migrate(&workspace).unwrap(), 1);
+
+    assert_eq!(fs::read_to_string(workspace.join("data.v1.json.bak"))
+        .unwrap(), original);
+    assert_eq!(requests(&workspace).len(), 2);
+    assert_eq!(version(&workspace).trim(), SCHEMA_VERSION.to_string());
+
+    fs::remove_dir_all(workspace).ok();
+}
+
+#[test]
+fn upgrades_every_store() {
+    let workspace = workspace_from("v1", "upgrades_every_store");
+    let request = r#"{"name":"Get users","req_type":"GET","url":"/users",
+        "body":""}"#;
+    fs::write(workspace.join("templates.json"),
+        format!(r#"{{"t1":{}}}"#, request)).unwrap();
+    fs::write(workspace.join("environments.json"),
+        r#"{"e1":{"name":"Local","variables":{},"db_id":"e1"}}"#).unwrap();
+    fs::create_dir_all(workspace.join("requests").join("users")).unwrap();
+    fs::write(workspace.join("requests").join("users").join("get.json"),
+        request).unwrap();
+
+    assert_eq!(migration::migrate(&workspace).unwrap(), 1);
+
+    for backup in ["templates.v1.json.bak", "environments.v1.json.bak",
+        "requests/users/get.v1.json.bak"] {
+
+        assert!(workspace.join(backup).exists(), "{} is missing", backup);
+    }
+
+    let templates: BTreeMap<String, Request> = serde_json::from_str(
+        &fs::read_to_string(workspace.join("templates.json")).unwrap())
+        .unwrap();
+    assert_eq!(templates["t1"].content_type, "text/plain");
+
+    // Request files are upgraded without the fields that change every run
+    let file: serde_json::Value = serde_json::from_str(&fs::read_to_string(
+        workspace.join("requests").join("users").join("get.json")).unwrap())
+        .unwrap();
+    assert_eq!(file["body_mode"], "Raw");
+    assert!(file.get("resp").is_none());
+    assert!(file.get("db_id").is_none());
+
+    fs::remove_dir_all(workspace).ok();
+}